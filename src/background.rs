@@ -0,0 +1,141 @@
+use std::process::{Child, Stdio};
+use std::sync::Mutex;
+
+use crate::command::{Command, CommandKind};
+use crate::error::ShellError;
+use crate::external::spawn;
+use crate::typesystem::Type;
+
+// Background jobs are child processes, so they can't change the shell's cwd or environment
+struct Job {
+    id: usize,
+    command: String,
+    child: Child,
+}
+
+static JOBS: Mutex<Vec<Job>> = Mutex::new(Vec::new());
+
+impl Command {
+    // Only external commands run in the background, their output goes straight to the terminal
+    // unless it's redirected
+    pub fn run_background(&mut self) -> Type {
+        let mut command = match &self.kind {
+            CommandKind::Background(command) => (**command).clone(),
+            _ => unreachable!(),
+        };
+        if !matches!(command.kind, CommandKind::External { .. }) {
+            return ShellError::Type {
+                message: "Only external commands can run in the background".into(),
+            }
+            .into();
+        }
+        command.stdin = self.stdin.take();
+        let mut redirects = std::mem::take(&mut command.redirects);
+        let destination = match command.apply_redirects(&mut redirects) {
+            Ok(destination) => destination,
+            Err(error) => return error,
+        };
+
+        let name = match &command.kind {
+            CommandKind::External { name, .. } => name.value.clone(),
+            _ => unreachable!(),
+        };
+        let args = match command.run_args() {
+            Ok(args) => args,
+            Err(error) => return error,
+        };
+        // Jobs never read from the terminal
+        let stdin = match command.stdin_bytes() {
+            Ok(stdin) => stdin.unwrap_or_default(),
+            Err(error) => return error,
+        };
+        let stdout = destination.map_or(Stdio::inherit(), Stdio::from);
+        match spawn(&name, &args, Some(stdin), stdout, Stdio::inherit()) {
+            Ok((child, _)) => {
+                let command = std::iter::once(name)
+                    .chain(args)
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                add_job(child, command);
+                Type::Null
+            }
+            Err(e) => ShellError::from(e).into(),
+        }
+    }
+}
+
+fn add_job(child: Child, command: String) {
+    if let Ok(mut jobs) = JOBS.lock() {
+        let id = jobs.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+        eprintln!("[{}] {}", id, child.id());
+        jobs.push(Job { id, command, child });
+    }
+}
+
+// Finished jobs are reported before the next prompt
+pub fn reap_jobs() {
+    if let Ok(mut jobs) = JOBS.lock() {
+        jobs.retain_mut(|job| match job.child.try_wait() {
+            Ok(Some(status)) => {
+                match status.code() {
+                    Some(0) => eprintln!("[{}] Done {}", job.id, job.command),
+                    Some(code) => eprintln!("[{}] Exit {} {}", job.id, code, job.command),
+                    None => eprintln!("[{}] Killed {}", job.id, job.command),
+                }
+                false
+            }
+            Ok(None) => true,
+            Err(_) => false,
+        });
+    }
+}
+
+// Run as an exit hook, so no job outlives the shell
+pub fn kill_jobs() {
+    if let Ok(mut jobs) = JOBS.lock() {
+        for mut job in jobs.drain(..) {
+            let _ = job.child.kill();
+            let _ = job.child.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::script::run_line;
+
+    #[test]
+    fn test_run_background() {
+        let start = std::time::Instant::now();
+        assert_eq!(run_line("sleep 2 &"), Type::Null);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_run_background_redirect() {
+        let path = std::env::temp_dir().join("hug_shell_test_background_redirect");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            run_line(&format!("echo hi > {} &", path.display())),
+            Type::Null
+        );
+        for _ in 0..50 {
+            if std::fs::read_to_string(&path).is_ok_and(|contents| contents == "hi\n") {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hi\n");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_run_background_builtin() {
+        assert_eq!(
+            run_line("cd / &").exit_code(),
+            crate::builtin::BuiltinExitCode::InvalidArgument as i32
+        );
+    }
+}
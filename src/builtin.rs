@@ -4,14 +4,26 @@ use crate::typesystem::Type;
 #[derive(Debug, PartialEq, Clone)]
 pub enum Builtin {
     Cd,
+    Complete,
     Exit,
+    From,
     Ls,
+    Open,
     Pwd,
+    Save,
+    Source,
+    To,
 }
 
-#[derive(Debug)]
+// Exit codes are stable, new ones are only ever added
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum BuiltinExitCode {
     TooManyArguments = 1,
+    TooFewArguments = 2,
+    SyntaxError = 3,
+    InvalidArgument = 4,
+    ParseError = 5,
+    HomeDirNotFound = 25,
     FileNotFound = 50,
     PermissionDenied = 100,
     UnknownError = 200,
@@ -24,21 +36,24 @@ pub trait BuiltinExt {
 
 impl BuiltinExt for &str {
     fn is_builtin(&self) -> bool {
-        match self {
-            &"cd" => true,
-            &"exit" => true,
-            &"ls" => true,
-            &"pwd" => true,
-            _ => false,
-        }
+        matches!(
+            *self,
+            "cd" | "complete" | "exit" | "from" | "ls" | "open" | "pwd" | "save" | "source" | "to"
+        )
     }
 
     fn get_builtin(&self) -> Builtin {
         match self {
             &"cd" => Builtin::Cd,
+            &"complete" => Builtin::Complete,
             &"exit" => Builtin::Exit,
+            &"from" => Builtin::From,
             &"ls" => Builtin::Ls,
+            &"open" => Builtin::Open,
             &"pwd" => Builtin::Pwd,
+            &"save" => Builtin::Save,
+            &"source" => Builtin::Source,
+            &"to" => Builtin::To,
             name => panic!("`{name}` is not a builtin!"),
         }
     }
@@ -54,25 +69,20 @@ impl BuiltinExt for String {
     }
 }
 
-pub fn handle_builtin_error(e: std::io::Error) -> Type {
-    Type::Error {
-        message: e.to_string(),
-        code: match e.kind() {
-            std::io::ErrorKind::NotFound => BuiltinExitCode::FileNotFound as i32,
-            std::io::ErrorKind::PermissionDenied => BuiltinExitCode::PermissionDenied as i32,
-            _ => BuiltinExitCode::UnknownError as i32,
-        },
-    }
-}
-
 impl Command {
     pub fn run_builtin(&self) -> Type {
         match &self.kind {
             CommandKind::Builtin { builtin, .. } => match builtin {
                 Builtin::Cd => self.run_cd(),
-                Builtin::Exit => std::process::exit(0),
+                Builtin::Complete => self.run_complete(),
+                Builtin::Exit => self.run_exit(),
+                Builtin::From => self.run_from(),
                 Builtin::Ls => self.run_ls(),
+                Builtin::Open => self.run_open(),
                 Builtin::Pwd => self.run_pwd(),
+                Builtin::Save => self.run_save(),
+                Builtin::Source => self.run_source(),
+                Builtin::To => self.run_to(),
             },
             _ => Type::Null,
         }
@@ -85,19 +95,31 @@ mod tests {
 
     #[test]
     fn test_is_builtin() {
-        assert_eq!("cd".is_builtin(), true);
-        assert_eq!("exit".is_builtin(), true);
-        assert_eq!("ls".is_builtin(), true);
-        assert_eq!("pwd".is_builtin(), true);
-        assert_eq!("helloworld".is_builtin(), false);
+        assert!("cd".is_builtin());
+        assert!("complete".is_builtin());
+        assert!("exit".is_builtin());
+        assert!("from".is_builtin());
+        assert!("ls".is_builtin());
+        assert!("open".is_builtin());
+        assert!("pwd".is_builtin());
+        assert!("save".is_builtin());
+        assert!("source".is_builtin());
+        assert!("to".is_builtin());
+        assert!(!"helloworld".is_builtin());
     }
 
     #[test]
     fn test_get_builtin() {
         assert_eq!("cd".get_builtin(), Builtin::Cd);
+        assert_eq!("complete".get_builtin(), Builtin::Complete);
         assert_eq!("exit".get_builtin(), Builtin::Exit);
+        assert_eq!("from".get_builtin(), Builtin::From);
         assert_eq!("ls".get_builtin(), Builtin::Ls);
+        assert_eq!("open".get_builtin(), Builtin::Open);
         assert_eq!("pwd".get_builtin(), Builtin::Pwd);
+        assert_eq!("save".get_builtin(), Builtin::Save);
+        assert_eq!("source".get_builtin(), Builtin::Source);
+        assert_eq!("to".get_builtin(), Builtin::To);
     }
 
     #[test]
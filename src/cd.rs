@@ -2,36 +2,35 @@ use homedir::my_home;
 
 use std::path::PathBuf;
 
-use crate::builtin::{handle_builtin_error, BuiltinExitCode};
+use crate::builtin::BuiltinExitCode;
 use crate::command::Command;
+use crate::error::ShellError;
 use crate::typesystem::Type;
 
-enum CdExitCode {
-    HomeDirNotFound = 25,
-}
-
 impl Command {
     fn set_dir(&self, path: &PathBuf) -> Type {
         match std::env::set_current_dir(path) {
             Ok(_) => Type::Null,
-            Err(e) => handle_builtin_error(e),
+            Err(e) => ShellError::from(e).into(),
         }
     }
 
     fn set_home_dir(&self) -> Type {
         self.set_dir(match my_home() {
-            Ok(Some(ref path)) => &path,
+            Ok(Some(ref path)) => path,
             Ok(None) => {
-                return Type::Error {
+                return ShellError::Runtime {
                     message: "Could not find home directory".into(),
-                    code: CdExitCode::HomeDirNotFound as i32,
+                    code: BuiltinExitCode::HomeDirNotFound,
                 }
+                .into()
             }
             Err(e) => {
-                return Type::Error {
+                return ShellError::Runtime {
                     message: e.to_string(),
-                    code: BuiltinExitCode::UnknownError as i32,
+                    code: BuiltinExitCode::UnknownError,
                 }
+                .into()
             }
         })
     }
@@ -40,11 +39,15 @@ impl Command {
         let args = self.get_args();
         match args.len() {
             0 => self.set_home_dir(),
-            1 => self.set_dir(&PathBuf::from(args[0].run_as_arg())),
-            _ => Type::Error {
-                message: "Too many arguments".into(),
-                code: BuiltinExitCode::TooManyArguments as i32,
+            1 => match args[0].run_as_arg() {
+                Ok(path) => self.set_dir(&PathBuf::from(path)),
+                Err(e) => e,
             },
+            _ => ShellError::Runtime {
+                message: "Too many arguments".into(),
+                code: BuiltinExitCode::TooManyArguments,
+            }
+            .into(),
         }
     }
 }
@@ -1,57 +1,77 @@
 use crate::builtin::Builtin;
+use crate::error::ShellError;
+use crate::interpolation::{interpolate, Interpolation};
 use crate::lexer::Token;
+use crate::redirect::Redirect;
 
 #[derive(Debug, Clone)]
 pub struct Command {
     pub kind: CommandKind,
     pub stdin: Option<crate::typesystem::Type>,
+    pub redirects: Vec<Redirect>,
 }
 
 impl PartialEq for Command {
     fn eq(&self, other: &Self) -> bool {
-        self.kind == other.kind
+        self.kind == other.kind && self.redirects == other.redirects
     }
 }
 
-pub const UNKNOWN_ERROR_CODE: i32 = 200;
-
 impl Command {
     pub fn new(kind: CommandKind) -> Self {
-        Command { kind, stdin: None }
+        Command {
+            kind,
+            stdin: None,
+            redirects: Vec::new(),
+        }
     }
 
     pub fn run(&mut self) -> crate::typesystem::Type {
+        if !self.redirects.is_empty() {
+            return self.run_redirects();
+        }
         match &self.kind {
             CommandKind::Builtin { .. } => self.run_builtin(),
             CommandKind::External { .. } => self.run_external(),
 
             CommandKind::String(s) => crate::typesystem::Type::String(s.clone()),
+            CommandKind::InterpolatedString(parts) => match interpolate(parts) {
+                Ok(s) => crate::typesystem::Type::String(s),
+                Err(e) => e,
+            },
             CommandKind::Boolean(b) => crate::typesystem::Type::Boolean(*b),
             CommandKind::Integer(i) => crate::typesystem::Type::Integer(*i),
             CommandKind::Float(f) => crate::typesystem::Type::Float(*f),
 
-            CommandKind::Redirect { .. } => self.run_redirect(),
             CommandKind::Pipe { .. } => self.run_pipe(),
+            CommandKind::Subshell(_) => self.run_subshell(),
+            CommandKind::Block(_) => self.run_block(),
+            CommandKind::And { .. } | CommandKind::Or { .. } => self.run_conditional(),
+            CommandKind::Background(_) => self.run_background(),
+            CommandKind::Field { .. } => self.run_field(),
 
             CommandKind::None => crate::typesystem::Type::Null,
 
-            CommandKind::Error(e) => crate::typesystem::Type::Error {
-                message: e.clone(),
-                code: UNKNOWN_ERROR_CODE,
-            },
+            CommandKind::Error(e) => e.clone().into(),
         }
     }
 
-    pub fn run_as_arg(&self) -> String {
+    // A failing command substitution is returned as its error, so the command doesn't run
+    pub fn run_as_arg(&self) -> Result<String, crate::typesystem::Type> {
         match &self.kind {
-            CommandKind::String(s) => s.clone(),
-            CommandKind::Boolean(b) => b.to_string(),
-            CommandKind::Integer(i) => i.to_string(),
-            CommandKind::Float(f) => f.to_string(),
+            CommandKind::String(s) => Ok(s.clone()),
+            CommandKind::InterpolatedString(parts) => interpolate(parts),
+            CommandKind::Boolean(b) => Ok(b.to_string()),
+            CommandKind::Integer(i) => Ok(i.to_string()),
+            CommandKind::Float(f) => Ok(f.to_string()),
             _ => unreachable!(),
         }
     }
 
+    pub fn run_args(&self) -> Result<Vec<String>, crate::typesystem::Type> {
+        self.get_args().iter().map(|arg| arg.run_as_arg()).collect()
+    }
+
     pub fn get_args(&self) -> Vec<Command> {
         match &self.kind {
             CommandKind::Builtin { args, .. } => args.clone(),
@@ -73,21 +93,39 @@ pub enum CommandKind {
     },
 
     String(String),
+    InterpolatedString(Vec<Interpolation>),
     Boolean(bool),
     Integer(i64),
     Float(f64),
 
-    Redirect {
-        source: Box<Command>,
-        destination: Box<Command>,
-    },
     Pipe {
         source: Box<Command>,
         destination: Box<Command>,
     },
 
+    // `( ... )` runs in an isolated shell state, `{ ... }` in the current one
+    Subshell(Vec<Command>),
+    Block(Vec<Command>),
+
+    // `right` only runs if `left` succeeded for `&&`, or failed for `||`
+    And {
+        left: Box<Command>,
+        right: Box<Command>,
+    },
+    Or {
+        left: Box<Command>,
+        right: Box<Command>,
+    },
+    Background(Box<Command>),
+
+    // `(cmd).field`, with a field for every `.`
+    Field {
+        target: Box<Command>,
+        fields: Vec<String>,
+    },
+
     None,
-    Error(String),
+    Error(ShellError),
 }
 
 #[cfg(test)]
@@ -97,6 +135,7 @@ mod tests {
         let mut command = super::Command {
             kind: super::CommandKind::String("Hello, world!".into()),
             stdin: None,
+            redirects: Vec::new(),
         };
         let output = command.run();
         match output {
@@ -112,11 +151,12 @@ mod tests {
         let mut command = super::Command {
             kind: super::CommandKind::Boolean(true),
             stdin: None,
+            redirects: Vec::new(),
         };
         let output = command.run();
         match output {
             crate::typesystem::Type::Boolean(b) => {
-                assert_eq!(b, true);
+                assert!(b);
             }
             _ => panic!("Expected Type::Boolean"),
         }
@@ -127,6 +167,7 @@ mod tests {
         let mut command = super::Command {
             kind: super::CommandKind::Integer(42),
             stdin: None,
+            redirects: Vec::new(),
         };
         let output = command.run();
         match output {
@@ -138,10 +179,12 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_run_float() {
         let mut command = super::Command {
             kind: super::CommandKind::Float(3.14),
             stdin: None,
+            redirects: Vec::new(),
         };
         let output = command.run();
         match output {
@@ -151,4 +194,18 @@ mod tests {
             _ => panic!("Expected Type::Float"),
         }
     }
+
+    #[test]
+    fn test_run_with_failing_substitution() {
+        let output = crate::script::run_line("echo \"x$(pwd 1)y\"");
+        match output {
+            crate::typesystem::Type::Error { code, .. } => {
+                assert_eq!(
+                    code,
+                    crate::builtin::BuiltinExitCode::TooManyArguments as i32
+                );
+            }
+            _ => panic!("Expected Type::Error, got {}", output),
+        }
+    }
 }
@@ -0,0 +1,87 @@
+use crate::builtin::BuiltinExitCode;
+use crate::command::Command;
+use crate::error::ShellError;
+use crate::typesystem::Type;
+
+impl Command {
+    // e.g. `cargo build | complete`, the source's stderr is collected alongside its stdout
+    pub fn run_complete(&self) -> Type {
+        if !self.get_args().is_empty() {
+            return ShellError::Runtime {
+                message: "Too many arguments".into(),
+                code: BuiltinExitCode::TooManyArguments,
+            }
+            .into();
+        }
+        match &self.stdin {
+            Some(Type::Output(o)) => complete(o),
+            // Errors of the shell, e.g. a command that wasn't found, complete with their message
+            Some(Type::Error { message, code, .. }) => Type::Record(vec![
+                ("stdout".to_string(), Type::String(String::new())),
+                ("stderr".to_string(), Type::String(message.clone())),
+                ("exit_code".to_string(), Type::Integer(*code as i64)),
+            ]),
+            _ => ShellError::Type {
+                message: "Expected the output of a command, e.g. `cargo build | complete`".into(),
+            }
+            .into(),
+        }
+    }
+}
+
+// `{stdout, stderr, exit_code}`, streams that aren't UTF-8 are binary
+pub fn complete(output: &std::process::Output) -> Type {
+    let stream = |bytes: &Vec<u8>| match String::from_utf8(bytes.clone()) {
+        Ok(s) => Type::String(s),
+        Err(e) => Type::Binary(e.into_bytes()),
+    };
+    Type::Record(vec![
+        ("stdout".to_string(), stream(&output.stdout)),
+        ("stderr".to_string(), stream(&output.stderr)),
+        (
+            "exit_code".to_string(),
+            Type::Integer(Type::Output(output.clone()).exit_code() as i64),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::script::run_line;
+
+    #[test]
+    fn test_run() {
+        assert_eq!(
+            run_line("sh -c 'echo out; echo err >&2; exit 3' | complete"),
+            Type::Record(vec![
+                ("stdout".to_string(), Type::String("out\n".to_string())),
+                ("stderr".to_string(), Type::String("err\n".to_string())),
+                ("exit_code".to_string(), Type::Integer(3)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_run_error() {
+        match run_line("hug_shell_test_missing_command | complete") {
+            Type::Record(fields) => {
+                assert_eq!(fields[2], ("exit_code".to_string(), Type::Integer(50)));
+            }
+            output => panic!("Expected Record, got {}", output),
+        }
+    }
+
+    #[test]
+    fn test_run_invalid() {
+        assert_eq!(
+            run_line("42 | complete").exit_code(),
+            BuiltinExitCode::InvalidArgument as i32
+        );
+        assert_eq!(
+            run_line("echo | complete now").exit_code(),
+            BuiltinExitCode::TooManyArguments as i32
+        );
+    }
+}
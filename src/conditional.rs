@@ -0,0 +1,51 @@
+use crate::command::{Command, CommandKind};
+use crate::script::print_output;
+use crate::typesystem::Type;
+
+impl Command {
+    pub fn run_conditional(&mut self) -> Type {
+        let (left, right, and) = match &mut self.kind {
+            CommandKind::And { left, right } => (left, right, true),
+            CommandKind::Or { left, right } => (left, right, false),
+            _ => unreachable!(),
+        };
+        left.stdin = self.stdin.take();
+
+        let output = left.run();
+        if (output.exit_code() == 0) != and {
+            return output;
+        }
+        print_output(&output);
+        right.run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::script::run_line;
+
+    #[test]
+    fn test_run_and() {
+        assert_eq!(run_line("1 && 'two'"), Type::String("two".to_string()));
+        assert_eq!(run_line("hug_shell_test_missing && 'two'").exit_code(), 50);
+    }
+
+    #[test]
+    fn test_run_or() {
+        assert_eq!(run_line("1 || 'two'"), Type::Integer(1));
+        assert_eq!(
+            run_line("hug_shell_test_missing || 'two'"),
+            Type::String("two".to_string())
+        );
+    }
+
+    #[test]
+    fn test_run_chain() {
+        assert_eq!(
+            run_line("hug_shell_test_missing && 1 || 'three'"),
+            Type::String("three".to_string())
+        );
+    }
+}
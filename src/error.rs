@@ -0,0 +1,135 @@
+use crate::builtin::BuiltinExitCode;
+use crate::span::Span;
+use crate::typesystem::Type;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ShellError {
+    // Input that cannot be split into tokens, e.g. an unterminated string
+    Lex {
+        message: String,
+        span: Span,
+    },
+    // Tokens in an order the grammar does not allow, e.g. `ls >`
+    Parse {
+        message: String,
+        span: Span,
+    },
+    Io {
+        message: String,
+        kind: std::io::ErrorKind,
+    },
+    // A value of the wrong type, e.g. `exit foo`
+    Type {
+        message: String,
+    },
+    Runtime {
+        message: String,
+        code: BuiltinExitCode,
+    },
+    // An external command that was killed by a signal
+    ExternalExit {
+        name: String,
+        signal: i32,
+    },
+}
+
+impl ShellError {
+    pub fn code(&self) -> i32 {
+        match self {
+            ShellError::Lex { .. } => BuiltinExitCode::SyntaxError as i32,
+            ShellError::Parse { .. } => BuiltinExitCode::ParseError as i32,
+            ShellError::Io { kind, .. } => match kind {
+                std::io::ErrorKind::NotFound => BuiltinExitCode::FileNotFound as i32,
+                std::io::ErrorKind::PermissionDenied => BuiltinExitCode::PermissionDenied as i32,
+                _ => BuiltinExitCode::UnknownError as i32,
+            },
+            ShellError::Type { .. } => BuiltinExitCode::InvalidArgument as i32,
+            ShellError::Runtime { code, .. } => *code as i32,
+            ShellError::ExternalExit { signal, .. } => 128 + signal,
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ShellError::Lex { span, .. } | ShellError::Parse { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ShellError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShellError::Lex { message, .. }
+            | ShellError::Parse { message, .. }
+            | ShellError::Io { message, .. }
+            | ShellError::Type { message }
+            | ShellError::Runtime { message, .. } => write!(f, "{}", message),
+            ShellError::ExternalExit { name, signal } => {
+                write!(f, "`{}` was terminated by signal {}", name, signal)
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for ShellError {
+    fn from(e: std::io::Error) -> Self {
+        ShellError::Io {
+            message: e.to_string(),
+            kind: e.kind(),
+        }
+    }
+}
+
+impl From<ShellError> for Type {
+    fn from(e: ShellError) -> Self {
+        Type::Error {
+            message: e.to_string(),
+            code: e.code(),
+            span: e.span(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes() {
+        let not_found = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert_eq!(ShellError::from(not_found).code(), 50);
+        assert_eq!(
+            ShellError::Parse {
+                message: "".into(),
+                span: Span::default()
+            }
+            .code(),
+            5
+        );
+        assert_eq!(
+            ShellError::ExternalExit {
+                name: "sleep".into(),
+                signal: 9
+            }
+            .code(),
+            137
+        );
+    }
+
+    #[test]
+    fn test_into_type() {
+        let error = ShellError::Lex {
+            message: "Syntax Error: Unterminated string".into(),
+            span: Span::new(5, 8),
+        };
+        assert_eq!(
+            Type::from(error),
+            Type::Error {
+                message: "Syntax Error: Unterminated string".into(),
+                code: 3,
+                span: Some(Span::new(5, 8)),
+            }
+        );
+    }
+}
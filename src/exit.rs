@@ -0,0 +1,161 @@
+use std::cell::Cell;
+use std::os::unix::process::ExitStatusExt;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::process::{ExitStatus, Output};
+use std::sync::Mutex;
+
+use crate::builtin::BuiltinExitCode;
+use crate::command::{Command, CommandKind};
+use crate::error::ShellError;
+use crate::typesystem::Type;
+
+type ExitHook = Box<dyn Fn() + Send>;
+
+static EXIT_HOOKS: Mutex<Vec<ExitHook>> = Mutex::new(Vec::new());
+
+pub fn add_exit_hook(hook: impl Fn() + Send + 'static) {
+    if let Ok(mut hooks) = EXIT_HOOKS.lock() {
+        hooks.push(Box::new(hook));
+    }
+}
+
+// Every way of leaving the shell goes through here so the hooks always run
+pub fn exit(code: i32) -> ! {
+    if let Ok(hooks) = EXIT_HOOKS.lock() {
+        for hook in hooks.iter() {
+            hook();
+        }
+    }
+    std::process::exit(code)
+}
+
+// Subshells run in the shell's own process, so an `exit` inside one unwinds to it with the
+// output so far instead of ending the process
+pub struct SubshellExit(pub Type);
+
+thread_local! {
+    static SUBSHELLS: Cell<usize> = const { Cell::new(0) };
+}
+
+pub fn in_subshell<T>(run: impl FnOnce() -> T) -> T {
+    SUBSHELLS.with(|depth| depth.set(depth.get() + 1));
+    let output = run();
+    SUBSHELLS.with(|depth| depth.set(depth.get() - 1));
+    output
+}
+
+// `Err` holds the output of a command that left the subshell
+pub fn catch_exit(run: impl FnOnce() -> Type) -> Result<Type, Type> {
+    if SUBSHELLS.with(|depth| depth.get()) == 0 {
+        return Ok(run());
+    }
+    match catch_unwind(AssertUnwindSafe(run)) {
+        Ok(output) => Ok(output),
+        Err(payload) => match payload.downcast::<SubshellExit>() {
+            Ok(exit) => Err(exit.0),
+            Err(payload) => resume_unwind(payload),
+        },
+    }
+}
+
+pub fn exit_subshell(output: Type) -> ! {
+    resume_unwind(Box::new(SubshellExit(output)))
+}
+
+fn leave(code: i32) -> ! {
+    if SUBSHELLS.with(|depth| depth.get()) == 0 {
+        exit(code);
+    }
+    exit_subshell(Type::Output(Output {
+        status: ExitStatus::from_raw((code & 0xff) << 8),
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+    }))
+}
+
+impl Command {
+    pub fn run_exit(&self) -> Type {
+        let args = self.get_args();
+        match args.len() {
+            0 => leave(0),
+            1 => match args[0].kind {
+                // Exit statuses are a single byte
+                CommandKind::Integer(code @ 0..=255) => leave(code as i32),
+                CommandKind::Integer(code) => ShellError::Runtime {
+                    message: format!("Exit code `{}` is out of range, expected 0 to 255", code),
+                    code: BuiltinExitCode::InvalidArgument,
+                }
+                .into(),
+                _ => match args[0].run_as_arg() {
+                    Ok(arg) => ShellError::Type {
+                        message: format!("Expected an exit code, found `{}`", arg),
+                    }
+                    .into(),
+                    Err(e) => e,
+                },
+            },
+            _ => ShellError::Runtime {
+                message: "Too many arguments".into(),
+                code: BuiltinExitCode::TooManyArguments,
+            }
+            .into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builtin::{Builtin, BuiltinExitCode};
+    use crate::command::{Command, CommandKind};
+    use crate::typesystem::Type;
+
+    #[test]
+    fn test_run_with_invalid_arg() {
+        let output = Command::new(CommandKind::Builtin {
+            builtin: Builtin::Exit,
+            args: vec![Command::new(CommandKind::String("one".to_string()))],
+        })
+        .run();
+        match output {
+            Type::Error { code, .. } => {
+                assert_eq!(code, BuiltinExitCode::InvalidArgument as i32);
+            }
+            _ => panic!("Expected Error, got {}", output),
+        }
+    }
+
+    #[test]
+    fn test_run_with_out_of_range_code() {
+        for code in [-1, 256, 4294967296] {
+            let output = Command::new(CommandKind::Builtin {
+                builtin: Builtin::Exit,
+                args: vec![Command::new(CommandKind::Integer(code))],
+            })
+            .run();
+            match output {
+                Type::Error { code, .. } => {
+                    assert_eq!(code, BuiltinExitCode::InvalidArgument as i32);
+                }
+                _ => panic!("Expected Error, got {}", output),
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_with_too_many_args() {
+        let output = Command::new(CommandKind::Builtin {
+            builtin: Builtin::Exit,
+            args: vec![
+                Command::new(CommandKind::Integer(1)),
+                Command::new(CommandKind::Integer(2)),
+            ],
+        })
+        .run();
+        match output {
+            Type::Error { code, .. } => {
+                assert_eq!(code, BuiltinExitCode::TooManyArguments as i32);
+            }
+            _ => panic!("Expected Error, got {}", output),
+        }
+    }
+}
@@ -1,47 +1,158 @@
-use std::io::Write;
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::ExitStatusExt;
+use std::path::Path;
+use std::process::{Child, ChildStderr, Stdio};
+use std::thread::JoinHandle;
 
-use crate::builtin::handle_builtin_error;
-use crate::command::{Command, CommandKind, UNKNOWN_ERROR_CODE};
+use crate::command::{Command, CommandKind};
+use crate::error::ShellError;
+use crate::from::hint;
+use crate::script::print_output;
+use crate::serialize::Format;
 use crate::typesystem::Type;
 
 impl Command {
     pub fn run_external(&mut self) -> Type {
+        let args = match self.run_args() {
+            Ok(args) => args,
+            Err(e) => return e,
+        };
+        let stdin = match self.stdin_bytes() {
+            Ok(stdin) => stdin,
+            Err(e) => return e,
+        };
         match &self.kind {
-            CommandKind::External { name, args } => {
-                match std::process::Command::new(name.value.clone())
-                    .args(&args.iter().map(|t| t.run_as_arg()).collect::<Vec<String>>())
-                    .stdin(match &self.stdin {
-                        Some(_) => std::process::Stdio::piped(),
-                        None => std::process::Stdio::inherit(),
-                    })
-                    .stdout(std::process::Stdio::piped())
-                    .spawn()
-                {
-                    Ok(mut child) => {
-                        match &self.stdin {
-                            Some(output) => {
-                                println!("Output: {:?}", output);
-                                let stdin = child.stdin.as_mut().unwrap();
-                                match stdin.write_all(&output.to_string().as_bytes()) {
-                                    Ok(_) => (),
-                                    Err(e) => {
-                                        return Type::Error {
-                                            message: e.to_string(),
-                                            code: UNKNOWN_ERROR_CODE,
-                                        }
-                                    }
+            CommandKind::External { name, .. } => {
+                match spawn(&name.value, &args, stdin, Stdio::piped(), Stdio::piped()) {
+                    Ok((mut child, writer)) => {
+                        let tee = child.stderr.take().map(tee_stderr);
+                        let mut output = child.wait_with_output();
+                        if let (Ok(output), Some(Ok(stderr))) =
+                            (&mut output, tee.map(|tee| tee.join()))
+                        {
+                            output.stderr = stderr;
+                        }
+                        if let Some(Ok(Err(e))) = writer.map(|writer| writer.join()) {
+                            return ShellError::from(e).into();
+                        }
+                        match output {
+                            Ok(output) => match output.status.signal() {
+                                Some(signal) => ShellError::ExternalExit {
+                                    name: name.value.clone(),
+                                    signal,
                                 }
-                            }
-                            _ => (),
+                                .into(),
+                                None => parse_hinted(&name.value, output),
+                            },
+                            Err(e) => ShellError::from(e).into(),
                         }
-                        Type::Output(child.wait_with_output().unwrap())
                     }
-                    Err(e) => handle_builtin_error(e),
+                    Err(e) => ShellError::from(e).into(),
                 }
             }
             _ => unreachable!(),
         }
     }
+
+    // The bytes an external command reads from its stdin, `None` leaves it on the terminal
+    pub fn stdin_bytes(&self) -> Result<Option<Vec<u8>>, Type> {
+        match &self.stdin {
+            // A failing command's error is shown, and the next command reads nothing
+            Some(error @ Type::Error { .. }) => {
+                print_output(error);
+                Ok(Some(Vec::new()))
+            }
+            Some(input) => match input.serialize(Format::from_env()) {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) => Err(e.into()),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+type StdinWriter = JoinHandle<std::io::Result<()>>;
+
+pub fn spawn(
+    name: &str,
+    args: &[String],
+    stdin: Option<Vec<u8>>,
+    stdout: Stdio,
+    stderr: Stdio,
+) -> std::io::Result<(Child, Option<StdinWriter>)> {
+    let mut child = std::process::Command::new(name)
+        .args(args)
+        .stdin(match &stdin {
+            Some(_) => Stdio::piped(),
+            None => Stdio::inherit(),
+        })
+        .stdout(stdout)
+        .stderr(stderr)
+        .spawn()?;
+    // Written from another thread so a child filling its stdout can't block us, commands that
+    // exit without reading their input aren't an error
+    let writer = match (stdin, child.stdin.take()) {
+        (Some(bytes), Some(mut pipe)) => {
+            Some(std::thread::spawn(move || match pipe.write_all(&bytes) {
+                Err(e) if e.kind() != ErrorKind::BrokenPipe => Err(e),
+                _ => Ok(()),
+            }))
+        }
+        _ => None,
+    };
+    Ok((child, writer))
+}
+
+// stderr is shown as the command writes it and also kept for `complete` and `.stderr`
+fn tee_stderr(mut pipe: ChildStderr) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut captured = Vec::new();
+        let mut buffer = [0; 4096];
+        loop {
+            match pipe.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => {
+                    let _ = std::io::stderr().write_all(&buffer[..n]);
+                    captured.extend_from_slice(&buffer[..n]);
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(_) => break,
+            }
+        }
+        captured
+    })
+}
+
+// Successful output of hinted commands joins the typed pipeline, anything that doesn't parse
+// is passed on as it is
+fn parse_hinted(name: &str, output: std::process::Output) -> Type {
+    match hint(name) {
+        Some(format) if output.status.success() => {
+            match format.parse(&String::from_utf8_lossy(&output.stdout)) {
+                Ok(value) => value,
+                Err(_) => Type::Output(output),
+            }
+        }
+        _ => Type::Output(output),
+    }
+}
+
+pub fn is_in_path(name: &str) -> bool {
+    if name.contains('/') {
+        return is_executable(Path::new(name));
+    }
+    match std::env::var_os("PATH") {
+        Some(paths) => std::env::split_paths(&paths).any(|dir| is_executable(&dir.join(name))),
+        None => false,
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    match path.metadata() {
+        Ok(metadata) => metadata.is_file() && metadata.permissions().mode() & 0o111 != 0,
+        Err(_) => false,
+    }
 }
 
 #[cfg(test)]
@@ -51,6 +162,7 @@ mod tests {
     use crate::builtin::BuiltinExitCode;
     use crate::command::CommandKind;
     use crate::lexer::{Token, TokenKind};
+    use crate::span::Span;
     use crate::typesystem::Type;
 
     #[test]
@@ -73,6 +185,7 @@ mod tests {
             name: Token {
                 value: "cat".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             args: vec![Command::new(CommandKind::String("Cargo.toml".to_string()))],
         })
@@ -89,12 +202,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_is_in_path() {
+        assert!(is_in_path("sh"));
+        assert!(is_in_path("/bin/sh"));
+        assert!(!is_in_path("helloworld"));
+        assert!(!is_in_path("./helloworld"));
+    }
+
     #[test]
     fn test_run_with_invalid_command() {
         let output = Command::new(CommandKind::External {
             name: Token {
                 value: "invalid".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             args: vec![],
         })
@@ -113,6 +235,7 @@ mod tests {
             name: Token {
                 value: "echo".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             args: vec![Command::new(CommandKind::String(
                 "Hello, world!".to_string(),
@@ -134,6 +257,7 @@ mod tests {
             name: Token {
                 value: "echo".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             args: vec![Command::new(CommandKind::Integer(42))],
         })
@@ -148,11 +272,13 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_run_echo_with_float() {
         let output = Command::new(CommandKind::External {
             name: Token {
                 value: "echo".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             args: vec![Command::new(CommandKind::Float(3.14))],
         })
@@ -172,6 +298,7 @@ mod tests {
             name: Token {
                 value: "echo".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             args: vec![Command::new(CommandKind::Boolean(true))],
         })
@@ -184,4 +311,76 @@ mod tests {
             _ => panic!("Expected Type::Output"),
         }
     }
+
+    #[test]
+    fn test_run_killed_by_signal() {
+        let output = Command::new(CommandKind::External {
+            name: Token {
+                value: "sh".to_string(),
+                kind: TokenKind::Word,
+                span: Span::default(),
+            },
+            args: vec![
+                Command::new(CommandKind::String("-c".to_string())),
+                Command::new(CommandKind::String("kill -9 $$".to_string())),
+            ],
+        })
+        .run();
+        assert_eq!(
+            output,
+            Type::Error {
+                message: "`sh` was terminated by signal 9".to_string(),
+                code: 137,
+                span: None,
+            }
+        );
+    }
+
+    fn cat(stdin: Type) -> Vec<u8> {
+        let mut command = Command::new(CommandKind::External {
+            name: Token {
+                value: "cat".to_string(),
+                kind: TokenKind::Word,
+                span: Span::default(),
+            },
+            args: Vec::new(),
+        });
+        command.stdin = Some(stdin);
+        match command.run() {
+            Type::Output(o) => o.stdout,
+            output => panic!("Expected Type::Output, got {}", output),
+        }
+    }
+
+    #[test]
+    fn test_run_with_stdin() {
+        assert_eq!(
+            cat(Type::String("Hello, world!".to_string())),
+            b"Hello, world!"
+        );
+        assert_eq!(
+            cat(Type::Array(vec![Type::Integer(1), Type::Integer(2)])),
+            b"1\n2\n"
+        );
+    }
+
+    #[test]
+    fn test_run_with_large_stdin() {
+        let input = "x".repeat(1 << 20);
+        assert_eq!(cat(Type::String(input.clone())), input.into_bytes());
+    }
+
+    #[test]
+    fn test_run_ignores_unread_stdin() {
+        let mut command = Command::new(CommandKind::External {
+            name: Token {
+                value: "true".to_string(),
+                kind: TokenKind::Word,
+                span: Span::default(),
+            },
+            args: Vec::new(),
+        });
+        command.stdin = Some(Type::String("x".repeat(1 << 20)));
+        assert_eq!(command.run().exit_code(), 0);
+    }
 }
@@ -0,0 +1,90 @@
+use crate::command::{Command, CommandKind};
+use crate::complete::complete;
+use crate::error::ShellError;
+use crate::typesystem::Type;
+
+impl Command {
+    // e.g. `(cargo build).stderr`, a command's output has the fields of `complete`
+    pub fn run_field(&mut self) -> Type {
+        match &mut self.kind {
+            CommandKind::Field { target, fields } => {
+                let mut value = target.run();
+                for field in fields.iter() {
+                    let record = match value {
+                        error @ Type::Error { .. } => return error,
+                        Type::Output(o) => complete(&o),
+                        value => value,
+                    };
+                    value = match record {
+                        Type::Record(r) => match r.iter().find(|(key, _)| key == field) {
+                            Some((_, value)) => value.clone(),
+                            None => {
+                                let keys = r
+                                    .iter()
+                                    .map(|(key, _)| format!("`{}`", key))
+                                    .collect::<Vec<String>>();
+                                return ShellError::Type {
+                                    message: format!(
+                                        "No field `{}`, expected one of {}",
+                                        field,
+                                        keys.join(", ")
+                                    ),
+                                }
+                                .into();
+                            }
+                        },
+                        value => {
+                            return ShellError::Type {
+                                message: format!(
+                                    "Only records and command output have fields, found {}",
+                                    value.to_colorless_string()
+                                ),
+                            }
+                            .into()
+                        }
+                    };
+                }
+                value
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::script::run_line;
+
+    #[test]
+    fn test_run() {
+        let line = "(sh -c 'echo out; echo err >&2; exit 3')";
+        assert_eq!(
+            run_line(&format!("{}.stdout", line)),
+            Type::String("out\n".to_string())
+        );
+        assert_eq!(
+            run_line(&format!("{}.stderr", line)),
+            Type::String("err\n".to_string())
+        );
+        assert_eq!(run_line(&format!("{}.exit_code", line)), Type::Integer(3));
+    }
+
+    #[test]
+    fn test_run_nested() {
+        assert_eq!(
+            run_line("(echo '{\"a\": {\"b\": 1}}' | from json).a.b"),
+            Type::Integer(1)
+        );
+    }
+
+    #[test]
+    fn test_run_invalid() {
+        assert_eq!(
+            run_line("(echo).status").to_colorless_string(),
+            "Error: No field `status`, expected one of `stdout`, `stderr`, `exit_code`\nExited With status 4"
+        );
+        assert_eq!(run_line("(42).value").exit_code(), 4);
+    }
+}
@@ -0,0 +1,443 @@
+use std::path::Path;
+
+use crate::builtin::BuiltinExitCode;
+use crate::command::Command;
+use crate::error::ShellError;
+use crate::typesystem::Type;
+
+// Formats `from` parses text in, e.g. `cat data.json | from json`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum InputFormat {
+    Json,
+    Csv,
+    Tsv,
+    Lines,
+    Toml,
+    Yaml,
+    // Columns separated by runs of spaces under a header row, like the output of `ps`
+    Ssv,
+}
+
+const FORMAT_NAMES: &str = "json, csv, tsv, lines, toml, yaml or ssv";
+
+// The formats of known commands, used by hints that only name the command. Only output with
+// single-word headers, e.g. not `df`'s `Mounted on`, splits into the right columns as ssv
+const KNOWN_FORMATS: [(&str, InputFormat); 2] =
+    [("ps", InputFormat::Ssv), ("find", InputFormat::Lines)];
+
+impl InputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(InputFormat::Json),
+            "csv" => Some(InputFormat::Csv),
+            "tsv" => Some(InputFormat::Tsv),
+            "lines" => Some(InputFormat::Lines),
+            "toml" => Some(InputFormat::Toml),
+            "yaml" | "yml" => Some(InputFormat::Yaml),
+            "ssv" => Some(InputFormat::Ssv),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InputFormat::Json => "JSON",
+            InputFormat::Csv => "CSV",
+            InputFormat::Tsv => "TSV",
+            InputFormat::Lines => "lines",
+            InputFormat::Toml => "TOML",
+            InputFormat::Yaml => "YAML",
+            InputFormat::Ssv => "SSV",
+        }
+    }
+
+    // Only interchange formats, files with any other extension are text
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "lines" | "ssv" => None,
+            extension => InputFormat::from_name(extension),
+        }
+    }
+
+    pub fn parse(self, text: &str) -> Result<Type, ShellError> {
+        let parsed = match self {
+            InputFormat::Json => serde_json::from_str(text)
+                .map(from_json)
+                .map_err(|e| e.to_string()),
+            InputFormat::Csv => from_csv(text, b','),
+            InputFormat::Tsv => from_csv(text, b'\t'),
+            InputFormat::Lines => Ok(Type::Array(
+                text.lines()
+                    .map(|line| Type::String(line.to_string()))
+                    .collect(),
+            )),
+            InputFormat::Toml => text
+                .parse::<toml::Table>()
+                .map(|table| from_toml(toml::Value::Table(table)))
+                .map_err(|e| e.to_string()),
+            InputFormat::Yaml => serde_yaml_ng::from_str(text)
+                .map(from_yaml)
+                .map_err(|e| e.to_string()),
+            InputFormat::Ssv => Ok(from_ssv(text)),
+        };
+        parsed.map_err(|message| ShellError::Runtime {
+            message: format!("Couldn't parse {}: {}", self.name(), message.trim()),
+            code: BuiltinExitCode::ParseError,
+        })
+    }
+}
+
+// Output of the commands listed in `HUG_FROM_HINTS` is parsed automatically, e.g.
+//   HUG_FROM_HINTS="ps,find,mytool=json"
+// A command named on its own uses the format it is known for
+pub fn hint(command: &str) -> Option<InputFormat> {
+    hint_in(&std::env::var("HUG_FROM_HINTS").ok()?, command)
+}
+
+fn hint_in(hints: &str, command: &str) -> Option<InputFormat> {
+    let name = Path::new(command).file_name()?.to_str()?;
+    let hint = hints.split(',').map(str::trim).find(|hint| {
+        hint.split_once('=')
+            .map_or(*hint, |(command, _)| command.trim())
+            == name
+    })?;
+    match hint.split_once('=') {
+        Some((_, format)) => InputFormat::from_name(format.trim()),
+        None => KNOWN_FORMATS
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, format)| *format),
+    }
+}
+
+impl Command {
+    pub fn run_from(&self) -> Type {
+        let args = self.get_args();
+        let format = match args.as_slice() {
+            [] => {
+                return ShellError::Runtime {
+                    message: format!("Expected a format: {}", FORMAT_NAMES),
+                    code: BuiltinExitCode::TooFewArguments,
+                }
+                .into()
+            }
+            [format] => {
+                let name = match format.run_as_arg() {
+                    Ok(name) => name,
+                    Err(e) => return e,
+                };
+                match InputFormat::from_name(&name) {
+                    Some(format) => format,
+                    None => {
+                        return ShellError::Type {
+                            message: format!(
+                                "Unknown format `{}`, expected {}",
+                                name, FORMAT_NAMES
+                            ),
+                        }
+                        .into()
+                    }
+                }
+            }
+            _ => {
+                return ShellError::Runtime {
+                    message: "Too many arguments".into(),
+                    code: BuiltinExitCode::TooManyArguments,
+                }
+                .into()
+            }
+        };
+
+        let text =
+            match &self.stdin {
+                Some(Type::Output(o)) => String::from_utf8_lossy(&o.stdout).to_string(),
+                Some(Type::Binary(b)) => String::from_utf8_lossy(b).to_string(),
+                Some(Type::String(s)) => s.clone(),
+                Some(error @ Type::Error { .. }) => return error.clone(),
+                _ => return ShellError::Type {
+                    message:
+                        "Expected text or command output as input, e.g. `cat data.json | from json`"
+                            .into(),
+                }
+                .into(),
+            };
+        match format.parse(&text) {
+            Ok(value) => value,
+            Err(e) => e.into(),
+        }
+    }
+}
+
+fn from_json(value: serde_json::Value) -> Type {
+    match value {
+        serde_json::Value::Null => Type::Null,
+        serde_json::Value::Bool(b) => Type::Boolean(b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Type::Integer(i),
+            None => Type::Float(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        serde_json::Value::String(s) => Type::String(s),
+        serde_json::Value::Array(a) => Type::Array(a.into_iter().map(from_json).collect()),
+        serde_json::Value::Object(o) => Type::Record(
+            o.into_iter()
+                .map(|(key, value)| (key, from_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn from_toml(value: toml::Value) -> Type {
+    match value {
+        toml::Value::String(s) => Type::String(s),
+        toml::Value::Integer(i) => Type::Integer(i),
+        toml::Value::Float(fl) => Type::Float(fl),
+        toml::Value::Boolean(b) => Type::Boolean(b),
+        toml::Value::Datetime(d) => Type::String(d.to_string()),
+        toml::Value::Array(a) => Type::Array(a.into_iter().map(from_toml).collect()),
+        toml::Value::Table(t) => Type::Record(
+            t.into_iter()
+                .map(|(key, value)| (key, from_toml(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn from_yaml(value: serde_yaml_ng::Value) -> Type {
+    match value {
+        serde_yaml_ng::Value::Null => Type::Null,
+        serde_yaml_ng::Value::Bool(b) => Type::Boolean(b),
+        serde_yaml_ng::Value::Number(n) => match n.as_i64() {
+            Some(i) => Type::Integer(i),
+            None => Type::Float(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        serde_yaml_ng::Value::String(s) => Type::String(s),
+        serde_yaml_ng::Value::Sequence(s) => Type::Array(s.into_iter().map(from_yaml).collect()),
+        serde_yaml_ng::Value::Mapping(m) => Type::Record(
+            m.into_iter()
+                .map(|(key, value)| {
+                    let key = match from_yaml(key) {
+                        Type::String(s) => s,
+                        key => key.to_undecorated_string(),
+                    };
+                    (key, from_yaml(value))
+                })
+                .collect(),
+        ),
+        serde_yaml_ng::Value::Tagged(tagged) => from_yaml(tagged.value),
+    }
+}
+
+// Every row becomes a record keyed by the header row
+fn from_csv(text: &str, delimiter: u8) -> Result<Type, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        rows.push(Type::Record(
+            headers
+                .iter()
+                .zip(record.iter())
+                .map(|(key, value)| (key.to_string(), infer(value)))
+                .collect(),
+        ));
+    }
+    Ok(Type::Array(rows))
+}
+
+// The last column takes the rest of the line, so e.g. commands with arguments stay whole
+fn from_ssv(text: &str) -> Type {
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+    let headers = match lines.next() {
+        Some(header) => header.split_whitespace().collect::<Vec<&str>>(),
+        None => return Type::Array(Vec::new()),
+    };
+    Type::Array(
+        lines
+            .map(|line| {
+                Type::Record(
+                    headers
+                        .iter()
+                        .zip(split_columns(line, headers.len()))
+                        .map(|(key, value)| (key.to_string(), infer(value)))
+                        .collect(),
+                )
+            })
+            .collect(),
+    )
+}
+
+fn split_columns(line: &str, count: usize) -> Vec<&str> {
+    let mut columns = Vec::new();
+    let mut rest = line.trim();
+    while columns.len() + 1 < count && !rest.is_empty() {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        columns.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    if !rest.is_empty() {
+        columns.push(rest);
+    }
+    columns
+}
+
+// Fields of text formats are numbers or booleans when they look like one
+fn infer(value: &str) -> Type {
+    if let Ok(i) = value.parse() {
+        return Type::Integer(i);
+    }
+    match value.parse() {
+        Ok(fl) if value.contains(|c: char| c.is_ascii_digit()) => Type::Float(fl),
+        _ => match value {
+            "true" => Type::Boolean(true),
+            "false" => Type::Boolean(false),
+            _ => Type::String(value.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::script::run_line;
+
+    fn record(fields: &[(&str, Type)]) -> Type {
+        Type::Record(
+            fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_parse_json() {
+        assert_eq!(
+            InputFormat::Json
+                .parse(r#"{"name": "hug", "tags": [1, 2.5, true, null]}"#)
+                .unwrap(),
+            record(&[
+                ("name", Type::String("hug".to_string())),
+                (
+                    "tags",
+                    Type::Array(vec![
+                        Type::Integer(1),
+                        Type::Float(2.5),
+                        Type::Boolean(true),
+                        Type::Null
+                    ])
+                ),
+            ])
+        );
+        assert_eq!(
+            InputFormat::Json.parse("{").unwrap_err().code(),
+            BuiltinExitCode::ParseError as i32
+        );
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let expected = Type::Array(vec![
+            record(&[
+                ("name", Type::String("a b".to_string())),
+                ("size", Type::Integer(1)),
+            ]),
+            record(&[
+                ("name", Type::String("c".to_string())),
+                ("size", Type::Float(2.5)),
+            ]),
+        ]);
+        assert_eq!(
+            InputFormat::Csv
+                .parse("name,size\n\"a b\",1\nc,2.5\n")
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            InputFormat::Tsv
+                .parse("name\tsize\na b\t1\nc\t2.5")
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        assert_eq!(
+            InputFormat::Lines.parse("a\nb c\n").unwrap(),
+            Type::Array(vec![
+                Type::String("a".to_string()),
+                Type::String("b c".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        assert_eq!(
+            InputFormat::Toml
+                .parse("name = \"hug\"\n[package]\nversion = 1\n")
+                .unwrap(),
+            record(&[
+                ("name", Type::String("hug".to_string())),
+                ("package", record(&[("version", Type::Integer(1))])),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_yaml() {
+        assert_eq!(
+            InputFormat::Yaml
+                .parse("name: hug\nsizes:\n  - 1\n  - 2\n")
+                .unwrap(),
+            record(&[
+                ("name", Type::String("hug".to_string())),
+                (
+                    "sizes",
+                    Type::Array(vec![Type::Integer(1), Type::Integer(2)])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_ssv() {
+        assert_eq!(
+            InputFormat::Ssv
+                .parse("  PID TTY   CMD\n    1 ?     init --flag\n")
+                .unwrap(),
+            Type::Array(vec![record(&[
+                ("PID", Type::Integer(1)),
+                ("TTY", Type::String("?".to_string())),
+                ("CMD", Type::String("init --flag".to_string())),
+            ])])
+        );
+    }
+
+    #[test]
+    fn test_hint() {
+        let hints = "ps, /usr/bin/mytool=json,git=csv";
+        assert_eq!(hint_in(hints, "ps"), Some(InputFormat::Ssv));
+        assert_eq!(hint_in(hints, "/bin/ps"), Some(InputFormat::Ssv));
+        assert_eq!(hint_in(hints, "git"), Some(InputFormat::Csv));
+        assert_eq!(hint_in("ls,df", "df"), None);
+        assert_eq!(hint_in("mytool", "mytool"), None);
+    }
+
+    #[test]
+    fn test_run_from() {
+        assert_eq!(
+            run_line("echo '[1, 2]' | from json"),
+            Type::Array(vec![Type::Integer(1), Type::Integer(2)])
+        );
+        assert_eq!(
+            run_line("from xml").exit_code(),
+            BuiltinExitCode::InvalidArgument as i32
+        );
+    }
+}
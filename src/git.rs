@@ -0,0 +1,215 @@
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq)]
+pub struct GitStatus {
+    pub branch: String,
+    // `None` when the index is too large to check or can't be read
+    pub dirty: Option<bool>,
+}
+
+// Reads `.git` directly so the prompt does not have to spawn a `git` process
+pub fn git_status(dir: &Path) -> Option<GitStatus> {
+    let (git_dir, work_tree) = find_git_dir(dir)?;
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let branch = match head.trim().strip_prefix("ref: ") {
+        Some(reference) => reference
+            .strip_prefix("refs/heads/")
+            .unwrap_or(reference)
+            .to_string(),
+        None => head.trim().chars().take(7).collect(),
+    };
+    let dirty = match std::fs::read(git_dir.join("index")) {
+        Ok(index) => index_is_dirty(&index, &work_tree),
+        // A new repository has no index until something is added
+        Err(_) => Some(false),
+    };
+    Some(GitStatus { branch, dirty })
+}
+
+fn find_git_dir(dir: &Path) -> Option<(PathBuf, PathBuf)> {
+    for ancestor in dir.ancestors() {
+        let git = ancestor.join(".git");
+        if git.is_dir() {
+            return Some((git, ancestor.to_path_buf()));
+        }
+        // Worktrees and submodules use a `.git` file pointing at the real directory
+        if let Ok(contents) = std::fs::read_to_string(&git) {
+            if let Some(path) = contents.trim().strip_prefix("gitdir: ") {
+                return Some((ancestor.join(path), ancestor.to_path_buf()));
+            }
+        }
+    }
+    None
+}
+
+// Larger indexes aren't compared against the work tree, so a big checkout doesn't slow every
+// prompt, and their state is unknown
+const MAX_INDEX_ENTRIES: u32 = 10_000;
+
+// Compares the size and mtime, to the nanosecond, of every entry in a version 2, 3 or 4 index against the work
+// tree, stopping at the first change. Untracked files aren't looked for, so they never make the
+// work tree dirty
+fn index_is_dirty(index: &[u8], work_tree: &Path) -> Option<bool> {
+    let read_u32 = |offset: usize| -> Option<u32> {
+        Some(u32::from_be_bytes(
+            index.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+
+    if index.get(0..4) != Some(b"DIRC".as_slice()) {
+        return None;
+    }
+    let version = read_u32(4)?;
+    if !(2..=4).contains(&version) {
+        return None;
+    }
+    let count = read_u32(8)?;
+    if count > MAX_INDEX_ENTRIES {
+        return None;
+    }
+
+    let mut offset = 12;
+    let mut path = Vec::new();
+    for _ in 0..count {
+        let (mtime, mtime_nsec, size) = (
+            read_u32(offset + 8)?,
+            read_u32(offset + 12)?,
+            read_u32(offset + 36)?,
+        );
+        let flags = index.get(offset + 60..offset + 62)?;
+        let flags = u16::from_be_bytes([flags[0], flags[1]]);
+        let extended = if flags & 0x4000 != 0 { 2 } else { 0 };
+        let mut path_start = offset + 62 + extended;
+        // Version 4 paths drop a number of bytes from the end of the previous path and append
+        // the rest, without padding the entry
+        if version == 4 {
+            let (strip, len) = read_varint(index.get(path_start..)?)?;
+            path.truncate(path.len().saturating_sub(strip));
+            path_start += len;
+        } else {
+            path.clear();
+        }
+        let path_len = index.get(path_start..)?.iter().position(|b| *b == 0)?;
+        path.extend_from_slice(&index[path_start..path_start + path_len]);
+
+        let file = work_tree.join(String::from_utf8_lossy(&path).as_ref());
+        match std::fs::symlink_metadata(file) {
+            Ok(metadata) => {
+                if metadata.size() as u32 != size
+                    || metadata.mtime() as u32 != mtime
+                    || metadata.mtime_nsec() as u32 != mtime_nsec
+                {
+                    return Some(true);
+                }
+            }
+            Err(_) => return Some(true),
+        }
+
+        offset = match version {
+            4 => path_start + path_len + 1,
+            _ => offset + ((62 + extended + path_len + 8) & !7),
+        };
+    }
+    Some(false)
+}
+
+// The offset encoding git uses, returns the value and the number of bytes read
+fn read_varint(bytes: &[u8]) -> Option<(usize, usize)> {
+    let mut value = 0usize;
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 {
+            value = value.checked_add(1)?.checked_shl(7)?;
+        }
+        value |= (byte & 0x7f) as usize;
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_git_status() {
+        let dir = std::env::temp_dir().join("hug_shell_test_git_status");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        git(&dir, &["init", "-q", "-b", "feature"]);
+        std::fs::write(dir.join("src/main.rs"), "fn main() {}").unwrap();
+        git(&dir, &["add", "."]);
+
+        assert_eq!(
+            git_status(&dir.join("src")),
+            Some(GitStatus {
+                branch: "feature".to_string(),
+                dirty: Some(false)
+            })
+        );
+
+        // Same size, most likely within the same second
+        std::fs::write(dir.join("src/main.rs"), "fn mian() {}").unwrap();
+        assert_eq!(git_status(&dir).unwrap().dirty, Some(true));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_git_status_index_v4() {
+        let dir = std::env::temp_dir().join("hug_shell_test_git_index_v4");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        git(&dir, &["init", "-q"]);
+        for name in ["src/lexer.rs", "src/lib.rs", "src/main.rs"] {
+            std::fs::write(dir.join(name), name).unwrap();
+        }
+        git(&dir, &["add", "."]);
+        git(&dir, &["update-index", "--index-version", "4"]);
+        assert_eq!(git_status(&dir).unwrap().dirty, Some(false));
+
+        std::fs::write(dir.join("src/main.rs"), "changed").unwrap();
+        assert_eq!(git_status(&dir).unwrap().dirty, Some(true));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_index_too_large() {
+        let mut index = b"DIRC".to_vec();
+        index.extend(2u32.to_be_bytes());
+        index.extend((MAX_INDEX_ENTRIES + 1).to_be_bytes());
+        assert_eq!(index_is_dirty(&index, Path::new("/")), None);
+    }
+
+    #[test]
+    fn test_git_status_detached() {
+        let dir = std::env::temp_dir().join("hug_shell_test_git_detached");
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(
+            dir.join(".git/HEAD"),
+            "0123456789abcdef0123456789abcdef01234567\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            git_status(&dir),
+            Some(GitStatus {
+                branch: "0123456".to_string(),
+                dirty: Some(false)
+            })
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+use std::os::unix::process::ExitStatusExt;
+use std::process::{ExitStatus, Output};
+
+use crate::command::{Command, CommandKind};
+use crate::exit::{catch_exit, exit_subshell, in_subshell};
+use crate::script::print_output;
+use crate::serialize::Format;
+use crate::typesystem::Type;
+
+impl Command {
+    // Like a block, but the working directory and environment are restored afterwards
+    pub fn run_subshell(&mut self) -> Type {
+        let cwd = std::env::current_dir();
+        let env = std::env::vars_os().collect::<Vec<_>>();
+
+        // An `exit` inside only leaves the subshell
+        let (output, _) = in_subshell(|| self.run_commands());
+
+        if let Ok(cwd) = cwd {
+            let _ = std::env::set_current_dir(cwd);
+        }
+        for (key, _) in std::env::vars_os() {
+            if !env.iter().any(|(k, _)| *k == key) {
+                std::env::remove_var(key);
+            }
+        }
+        for (key, value) in env {
+            std::env::set_var(key, value);
+        }
+        output
+    }
+
+    pub fn run_block(&mut self) -> Type {
+        match self.run_commands() {
+            (output, false) => output,
+            // The enclosing subshell is left with the block's output
+            (output, true) => exit_subshell(output),
+        }
+    }
+
+    // The outputs of the commands are combined so the group can be piped or redirected as one,
+    // running stops at an `exit` that leaves a subshell
+    fn run_commands(&mut self) -> (Type, bool) {
+        let stdin = self.stdin.take();
+        let commands = match &mut self.kind {
+            CommandKind::Subshell(commands) | CommandKind::Block(commands) => commands,
+            _ => unreachable!(),
+        };
+        if let Some(first) = commands.first_mut() {
+            first.stdin = stdin;
+        }
+
+        let count = commands.len();
+        let mut outputs = Vec::new();
+        let mut status = 0;
+        let mut exited = false;
+        for (i, command) in commands.iter_mut().enumerate() {
+            let output = match catch_exit(|| command.run()) {
+                Ok(output) => output,
+                Err(output) => {
+                    exited = true;
+                    output
+                }
+            };
+            status = output.exit_code();
+            match output {
+                Type::Null => (),
+                // Errors go straight to the terminal, only a failing last command is kept
+                Type::Error { .. } if i + 1 < count => print_output(&output),
+                output => outputs.push(output),
+            }
+            if exited {
+                break;
+            }
+        }
+
+        let output = match outputs.len() {
+            0 => Type::Null,
+            1 => outputs.pop().unwrap(),
+            _ => combine(outputs, status),
+        };
+        (output, exited)
+    }
+}
+
+fn combine(outputs: Vec<Type>, status: i32) -> Type {
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    for output in outputs {
+        match output {
+            Type::Output(o) => {
+                stdout.extend(o.stdout);
+                stderr.extend(o.stderr);
+            }
+            // Shown like the stderr of an external command
+            Type::Error { .. } => {
+                print_output(&output);
+                stderr.extend(format!("{}\n", output.to_undecorated_string()).bytes())
+            }
+            // Written like the output of a single redirected command, ending its own line
+            output => match output.serialize(Format::from_env()) {
+                Ok(bytes) => {
+                    let end = !bytes.is_empty() && !bytes.ends_with(b"\n");
+                    stdout.extend(bytes);
+                    if end {
+                        stdout.push(b'\n');
+                    }
+                }
+                Err(e) => {
+                    let error = Type::from(e);
+                    print_output(&error);
+                    stderr.extend(format!("{}\n", error.to_undecorated_string()).bytes())
+                }
+            },
+        }
+    }
+    Type::Output(Output {
+        status: ExitStatus::from_raw((status & 0xff) << 8),
+        stdout,
+        stderr,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::script::run_line;
+
+    #[test]
+    fn test_run_block() {
+        match run_line("{ echo a; 1\n'b' }") {
+            Type::Output(output) => {
+                assert!(output.status.success());
+                assert_eq!(String::from_utf8(output.stdout).unwrap(), "a\n1\nb\n");
+            }
+            output => panic!("Expected Output, got {}", output),
+        }
+        assert_eq!(run_line("{ 1 }"), Type::Integer(1));
+        assert_eq!(run_line("{ }"), Type::Null);
+    }
+
+    #[test]
+    fn test_run_block_redirect() {
+        let path = std::env::temp_dir().join("hug_shell_test_block_redirect");
+        run_line(&format!("{{ echo a; echo b }} > {}", path.display()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nb\n");
+
+        run_line(&format!(
+            "{{ echo a; echo '[1, 2]' | from json }} > {}",
+            path.display()
+        ));
+        let array = Type::Array(vec![Type::Integer(1), Type::Integer(2)])
+            .serialize(Format::from_env())
+            .unwrap();
+        assert_eq!(
+            std::fs::read(&path).unwrap(),
+            [b"a\n".as_slice(), &array].concat()
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_run_block_exit_code() {
+        assert_eq!(
+            run_line("{ echo a; false_command_that_does_not_exist }").exit_code(),
+            50
+        );
+        assert_eq!(
+            run_line("{ false_command_that_does_not_exist; echo a }").exit_code(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_run_subshell() {
+        let cwd = std::env::current_dir().unwrap();
+        let output = run_line("(cd /; pwd)");
+        assert_eq!(output.to_undecorated_string().trim(), "/");
+        assert_eq!(std::env::current_dir().unwrap(), cwd);
+    }
+
+    #[test]
+    fn test_run_subshell_exit() {
+        match run_line("(echo a; { exit 3 }; echo b)") {
+            Type::Output(output) => {
+                assert_eq!(output.status.code(), Some(3));
+                assert_eq!(String::from_utf8(output.stdout).unwrap(), "a\n");
+            }
+            output => panic!("Expected Output, got {}", output),
+        }
+        assert_eq!(
+            run_line("(exit 3); 'after'"),
+            Type::String("after".to_string())
+        );
+    }
+}
@@ -0,0 +1,22 @@
+use rustyline::completion::Completer;
+use rustyline::Helper;
+
+use std::sync::{Arc, Mutex};
+
+use crate::history::History;
+
+pub struct ShellHelper {
+    pub history: Arc<Mutex<History>>,
+}
+
+impl ShellHelper {
+    pub fn new(history: Arc<Mutex<History>>) -> Self {
+        ShellHelper { history }
+    }
+}
+
+impl Helper for ShellHelper {}
+
+impl Completer for ShellHelper {
+    type Candidate = String;
+}
@@ -0,0 +1,168 @@
+use colored::Colorize;
+use rustyline::highlight::{CmdKind, Highlighter};
+
+use std::borrow::Cow;
+
+use crate::builtin::BuiltinExt;
+use crate::error::ShellError;
+use crate::external::is_in_path;
+use crate::helper::ShellHelper;
+use crate::lexer::{lex, Token, TokenKind, UNTERMINATED_STRING};
+use crate::span::Span;
+
+impl Highlighter for ShellHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        Cow::Owned(highlight_line(line))
+    }
+
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(hint.dimmed().to_string())
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, kind: CmdKind) -> bool {
+        kind != CmdKind::MoveCursor
+    }
+}
+
+fn highlight_line(line: &str) -> String {
+    match lex(line) {
+        Ok(tokens) => highlight_tokens(line, &tokens),
+        Err(ShellError::Lex { message, span }) if message == UNTERMINATED_STRING => {
+            let start = span.start;
+            let mut highlighted = highlight_line(&line[..start]);
+            highlighted.push_str(&line[start..].red().to_string());
+            highlighted
+        }
+        Err(_) => line.to_string(),
+    }
+}
+
+fn highlight_tokens(line: &str, tokens: &[Token]) -> String {
+    let mut highlighted = String::new();
+    let mut cursor = 0;
+    let mut command_position = true;
+
+    // Heredoc bodies come after the rest of their line in the source
+    let mut tokens = tokens.iter().collect::<Vec<&Token>>();
+    tokens.sort_by_key(|token| token.span.start);
+
+    for token in tokens {
+        let Span { start, end, .. } = token.span;
+        if start < cursor || end > line.len() {
+            continue;
+        }
+        highlighted.push_str(&line[cursor..start]);
+        highlighted.push_str(&color_token(token, &line[start..end], command_position));
+        cursor = end;
+
+        command_position = matches!(
+            token.kind,
+            TokenKind::Pipe
+                | TokenKind::And
+                | TokenKind::Or
+                | TokenKind::SemiColon
+                | TokenKind::Ampersand
+                | TokenKind::Newline
+                | TokenKind::LeftParen
+                | TokenKind::LeftBrace
+        );
+    }
+
+    highlighted.push_str(&line[cursor..]);
+    highlighted
+}
+
+fn color_token(token: &Token, raw: &str, command_position: bool) -> String {
+    match token.kind {
+        TokenKind::Word if command_position => {
+            if token.value.is_builtin() {
+                raw.yellow().bold().to_string()
+            } else if is_in_path(&token.value) {
+                raw.yellow().to_string()
+            } else {
+                raw.red().to_string()
+            }
+        }
+        TokenKind::Word | TokenKind::Newline => raw.to_string(),
+        TokenKind::String | TokenKind::InterpolatedString(_) => raw.green().to_string(),
+        TokenKind::Integer | TokenKind::Float => raw.cyan().to_string(),
+        TokenKind::Boolean => raw.bright_magenta().to_string(),
+        TokenKind::GreaterThan
+        | TokenKind::LessThan
+        | TokenKind::Pipe
+        | TokenKind::Heredoc
+        | TokenKind::HereString
+        | TokenKind::And
+        | TokenKind::Or
+        | TokenKind::SemiColon
+        | TokenKind::Ampersand
+        | TokenKind::LeftParen
+        | TokenKind::RightParen
+        | TokenKind::LeftBrace
+        | TokenKind::RightBrace => raw.blue().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_builtin() {
+        assert_eq!(
+            highlight_line("ls test_dir"),
+            format!("{} test_dir", "ls".yellow().bold())
+        );
+    }
+
+    #[test]
+    fn test_highlight_external() {
+        assert_eq!(highlight_line("sh"), "sh".yellow().to_string());
+        assert_eq!(highlight_line("helloworld"), "helloworld".red().to_string());
+    }
+
+    #[test]
+    fn test_highlight_literals() {
+        assert_eq!(
+            highlight_line("'hi' | cat; 42 true"),
+            format!(
+                "{} {} {}{} {} {}",
+                "'hi'".green(),
+                "|".blue(),
+                "cat".yellow(),
+                ";".blue(),
+                "42".cyan(),
+                "true".bright_magenta()
+            )
+        );
+    }
+
+    #[test]
+    fn test_highlight_unterminated_escaped_string() {
+        assert_eq!(
+            highlight_line("cd $'it\\'s' \"a\\\""),
+            format!("{} $'it\\'s' {}", "cd".yellow().bold(), "\"a\\\"".red())
+        );
+    }
+
+    #[test]
+    fn test_highlight_concatenated_word() {
+        assert_eq!(
+            highlight_line("cd --name=\"my file\" <<< $'\\t'"),
+            format!(
+                "{} --name=\"my file\" {} {}",
+                "cd".yellow().bold(),
+                "<<<".blue(),
+                "$'\\t'".green()
+            )
+        );
+    }
+
+    #[test]
+    fn test_highlight_unterminated_string() {
+        assert_eq!(
+            highlight_line("cd \"Hello"),
+            format!("{} {}", "cd".yellow().bold(), "\"Hello".red())
+        );
+    }
+}
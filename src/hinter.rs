@@ -0,0 +1,20 @@
+use rustyline::hint::Hinter;
+use rustyline::Context;
+
+use crate::helper::ShellHelper;
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<String> {
+        if line.is_empty() || pos < line.len() {
+            return None;
+        }
+        let cwd = std::env::current_dir().ok()?;
+        self.history
+            .lock()
+            .ok()?
+            .suggest(line, &cwd)
+            .map(|entry| entry[line.len()..].to_string())
+    }
+}
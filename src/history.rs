@@ -0,0 +1,227 @@
+use homedir::my_home;
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const HISTORY_SIZE: usize = 1000;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct HistoryEntry {
+    pub line: String,
+    pub cwd: PathBuf,
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    path: Option<PathBuf>,
+}
+
+pub fn history_path() -> Option<PathBuf> {
+    match my_home() {
+        Ok(Some(home)) => Some(home.join(".local/share/hug_shell/history")),
+        _ => None,
+    }
+}
+
+impl History {
+    // The file can hold more than `HISTORY_SIZE` entries until it's compacted
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let mut entries = match path.as_ref().map(read_entries) {
+            Some(Ok(entries)) => entries,
+            _ => Vec::new(),
+        };
+        entries.drain(..entries.len().saturating_sub(HISTORY_SIZE));
+        History { entries, path }
+    }
+
+    // Every entry is appended as it's pushed, so a shell that is killed keeps its history
+    fn append(&self, entry: &HistoryEntry) -> std::io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        file.write_all(format_entry(entry).as_bytes())
+    }
+
+    // Rewrites the file with only its last `HISTORY_SIZE` entries, including those other shells
+    // appended
+    pub fn compact(&self) -> std::io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let mut entries = match read_entries(path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        entries.drain(..entries.len().saturating_sub(HISTORY_SIZE));
+        // Written next to the file and renamed over it, so a failed write loses nothing
+        let compacted = path.with_extension("compact");
+        std::fs::write(
+            &compacted,
+            entries.iter().map(format_entry).collect::<String>(),
+        )?;
+        std::fs::rename(compacted, path)
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn push(&mut self, line: &str, cwd: PathBuf) -> std::io::Result<()> {
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        if let Some(last) = self.entries.last() {
+            if last.line == line && last.cwd == cwd {
+                return Ok(());
+            }
+        }
+        let entry = HistoryEntry {
+            line: line.to_string(),
+            cwd,
+        };
+        let appended = self.append(&entry);
+        self.entries.push(entry);
+        if self.entries.len() > HISTORY_SIZE {
+            self.entries.remove(0);
+        }
+        appended
+    }
+
+    // Prefer the most recent entry run in `cwd`, then the most recent anywhere
+    pub fn suggest(&self, prefix: &str, cwd: &Path) -> Option<&str> {
+        let matches =
+            |entry: &&HistoryEntry| entry.line.starts_with(prefix) && entry.line != prefix;
+        self.entries
+            .iter()
+            .rev()
+            .filter(matches)
+            .find(|entry| entry.cwd == cwd)
+            .or_else(|| self.entries.iter().rev().find(matches))
+            .map(|entry| entry.line.as_str())
+    }
+}
+
+fn read_entries(path: &PathBuf) -> std::io::Result<Vec<HistoryEntry>> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(contents.lines().filter_map(parse_entry).collect())
+}
+
+fn format_entry(entry: &HistoryEntry) -> String {
+    format!(
+        "{}\t{}\n",
+        escape(&entry.cwd.to_string_lossy()),
+        escape(&entry.line)
+    )
+}
+
+fn parse_entry(line: &str) -> Option<HistoryEntry> {
+    let (cwd, line) = line.split_once('\t')?;
+    Some(HistoryEntry {
+        line: unescape(line),
+        cwd: PathBuf::from(unescape(cwd)),
+    })
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::new();
+    let mut iter = s.chars();
+    while let Some(c) = iter.next() {
+        match c {
+            '\\' => match iter.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('t') => unescaped.push('\t'),
+                Some(c) => unescaped.push(c),
+                None => unescaped.push('\\'),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_prefers_cwd() {
+        let mut history = History::default();
+        history
+            .push("ls test_dir", PathBuf::from("/project"))
+            .unwrap();
+        history.push("ls src", PathBuf::from("/other")).unwrap();
+
+        assert_eq!(
+            history.suggest("ls", Path::new("/project")),
+            Some("ls test_dir")
+        );
+        assert_eq!(history.suggest("ls", Path::new("/other")), Some("ls src"));
+        assert_eq!(history.suggest("ls", Path::new("/tmp")), Some("ls src"));
+        assert_eq!(history.suggest("ls src", Path::new("/tmp")), None);
+        assert_eq!(history.suggest("cd", Path::new("/tmp")), None);
+    }
+
+    #[test]
+    fn test_push_skips_empty_and_duplicates() {
+        let mut history = History::default();
+        history.push("  ", PathBuf::from("/")).unwrap();
+        history.push("pwd", PathBuf::from("/")).unwrap();
+        history.push("pwd", PathBuf::from("/")).unwrap();
+
+        assert_eq!(history.entries().len(), 1);
+    }
+
+    #[test]
+    fn test_push_and_load() {
+        let path = std::env::temp_dir().join("hug_shell_test_history");
+        let _ = std::fs::remove_file(&path);
+        let mut history = History::load(Some(path.clone()));
+        history
+            .push("echo 'a\tb\nc' \\", PathBuf::from("/project"))
+            .unwrap();
+
+        // Nothing else is written before the shell exits
+        let loaded = History::load(Some(path.clone()));
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.entries(), history.entries());
+    }
+
+    #[test]
+    fn test_compact() {
+        let path = std::env::temp_dir().join("hug_shell_test_history_compact");
+        let _ = std::fs::remove_file(&path);
+        let mut history = History::load(Some(path.clone()));
+        for i in 0..HISTORY_SIZE + 2 {
+            history
+                .push(&format!("echo {}", i), PathBuf::from("/"))
+                .unwrap();
+        }
+        let lines = |path: &PathBuf| std::fs::read_to_string(path).unwrap().lines().count();
+        assert_eq!(lines(&path), HISTORY_SIZE + 2);
+
+        history.compact().unwrap();
+        assert_eq!(lines(&path), HISTORY_SIZE);
+        assert_eq!(
+            History::load(Some(path.clone())).entries(),
+            history.entries()
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,84 @@
+use crate::command::Command;
+use crate::typesystem::Type;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Interpolation {
+    Literal(String),
+    Variable(String),
+    Command(Vec<Command>),
+}
+
+// Builds the string, or returns the first error raised by a command substitution
+pub fn interpolate(parts: &[Interpolation]) -> Result<String, Type> {
+    let mut string = String::new();
+    for part in parts {
+        match part {
+            Interpolation::Literal(s) => string.push_str(s),
+            Interpolation::Variable(name) => {
+                string.push_str(&std::env::var(name).unwrap_or_default())
+            }
+            Interpolation::Command(commands) => {
+                let mut output = Type::Null;
+                for command in commands.clone().iter_mut() {
+                    output = command.run();
+                    if let Type::Error { .. } = output {
+                        return Err(output);
+                    }
+                }
+                if output != Type::Null {
+                    string.push_str(output.to_undecorated_string().trim_end_matches('\n'));
+                }
+            }
+        }
+    }
+    Ok(string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::builtin::{Builtin, BuiltinExitCode};
+    use crate::command::CommandKind;
+    use crate::lexer::{Token, TokenKind};
+    use crate::span::Span;
+
+    #[test]
+    fn test_interpolate() {
+        std::env::set_var("HUG_SHELL_TEST_NAME", "world");
+        let output = Command::new(CommandKind::InterpolatedString(vec![
+            Interpolation::Literal("Hello, ".to_string()),
+            Interpolation::Variable("HUG_SHELL_TEST_NAME".to_string()),
+            Interpolation::Variable("HUG_SHELL_TEST_UNSET".to_string()),
+            Interpolation::Literal("! ".to_string()),
+            Interpolation::Command(vec![Command::new(CommandKind::External {
+                name: Token {
+                    value: "echo".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                args: vec![Command::new(CommandKind::Integer(42))],
+            })]),
+        ]))
+        .run();
+        assert_eq!(output, Type::String("Hello, world! 42".to_string()));
+    }
+
+    #[test]
+    fn test_interpolate_error() {
+        let output = Command::new(CommandKind::InterpolatedString(vec![
+            Interpolation::Literal("in ".to_string()),
+            Interpolation::Command(vec![Command::new(CommandKind::Builtin {
+                builtin: Builtin::Pwd,
+                args: vec![Command::new(CommandKind::Integer(1))],
+            })]),
+        ]))
+        .run();
+        match output {
+            Type::Error { code, .. } => {
+                assert_eq!(code, BuiltinExitCode::TooManyArguments as i32);
+            }
+            _ => panic!("Expected Error, got {}", output),
+        }
+    }
+}
@@ -1,7 +1,21 @@
-#[derive(Debug, PartialEq, Clone)]
+use crate::error::ShellError;
+use crate::span::Span;
+
+pub const UNTERMINATED_STRING: &str = "Syntax Error: Unterminated string";
+pub const UNTERMINATED_HEREDOC: &str = "Syntax Error: Unterminated heredoc";
+
+#[derive(Debug, Clone)]
 pub struct Token {
     pub value: String,
     pub kind: TokenKind,
+    pub span: Span,
+}
+
+// Tokens are equal regardless of where they appear in the source
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value && self.kind == other.kind
+    }
 }
 
 impl Token {
@@ -10,131 +24,633 @@ impl Token {
             ">" => Token {
                 value: value.to_string(),
                 kind: TokenKind::GreaterThan,
+                span: Span::default(),
+            },
+            "<" => Token {
+                value: value.to_string(),
+                kind: TokenKind::LessThan,
+                span: Span::default(),
             },
             "|" => Token {
                 value: value.to_string(),
                 kind: TokenKind::Pipe,
+                span: Span::default(),
             },
             ";" => Token {
                 value: value.to_string(),
                 kind: TokenKind::SemiColon,
+                span: Span::default(),
+            },
+            "&" => Token {
+                value: value.to_string(),
+                kind: TokenKind::Ampersand,
+                span: Span::default(),
+            },
+            "&&" => Token {
+                value: value.to_string(),
+                kind: TokenKind::And,
+                span: Span::default(),
+            },
+            "||" => Token {
+                value: value.to_string(),
+                kind: TokenKind::Or,
+                span: Span::default(),
+            },
+            "\n" => Token {
+                value: value.to_string(),
+                kind: TokenKind::Newline,
+                span: Span::default(),
+            },
+            "(" => Token {
+                value: value.to_string(),
+                kind: TokenKind::LeftParen,
+                span: Span::default(),
+            },
+            ")" => Token {
+                value: value.to_string(),
+                kind: TokenKind::RightParen,
+                span: Span::default(),
+            },
+            "{" => Token {
+                value: value.to_string(),
+                kind: TokenKind::LeftBrace,
+                span: Span::default(),
+            },
+            "}" => Token {
+                value: value.to_string(),
+                kind: TokenKind::RightBrace,
+                span: Span::default(),
+            },
+            "<<" | "<<-" => Token {
+                value: value.to_string(),
+                kind: TokenKind::Heredoc,
+                span: Span::default(),
+            },
+            "<<<" => Token {
+                value: value.to_string(),
+                kind: TokenKind::HereString,
+                span: Span::default(),
             },
             "true" | "false" => Token {
                 value: value.to_string(),
                 kind: TokenKind::Boolean,
+                span: Span::default(),
             },
             _ => {
-                if value.starts_with('\'') && value.ends_with('\'') {
-                    Token {
-                        value: value[1..value.len() - 1].to_string(),
-                        kind: TokenKind::String,
-                    }
-                } else if value.starts_with('"') && value.ends_with('"') {
+                if (value.starts_with('\'') && value.ends_with('\''))
+                    || (value.starts_with('"') && value.ends_with('"'))
+                {
                     Token {
                         value: value[1..value.len() - 1].to_string(),
                         kind: TokenKind::String,
+                        span: Span::default(),
                     }
                 } else if value.parse::<i64>().is_ok() {
                     Token {
                         value: value.to_string(),
                         kind: TokenKind::Integer,
+                        span: Span::default(),
                     }
                 } else if value.parse::<f64>().is_ok() {
                     Token {
                         value: value.to_string(),
                         kind: TokenKind::Float,
+                        span: Span::default(),
                     }
                 } else {
                     Token {
                         value: value.to_string(),
                         kind: TokenKind::Word,
+                        span: Span::default(),
                     }
                 }
             }
         }
     }
+
+    pub fn with_span(self, span: Span) -> Self {
+        Token { span, ..self }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenKind {
     Word,
     String,
+    // A word containing `$` expansions in double quotes, its value is the source text of the word
+    InterpolatedString(Vec<StringPart>),
     Boolean,
     Integer,
     Float,
 
     GreaterThan,
+    LessThan,
     Pipe,
+    // Always followed by the heredoc body as a String or InterpolatedString token
+    Heredoc,
+    HereString,
+    And,
+    Or,
 
     SemiColon,
+    // Ends a statement like `;`, but runs it in the background
+    Ampersand,
+    Newline,
+
+    LeftParen,
+    RightParen,
+    // Only a `{` or `}` standing on its own, `{a}` stays a word
+    LeftBrace,
+    RightBrace,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum StringPart {
+    Literal(String),
+    Variable(String),
+    Command(Vec<Token>),
+}
+
+// A word is built from unquoted characters, escapes and quoted segments, e.g. `--name="my file"`
+#[derive(Debug, Default)]
+struct Word {
+    start: Option<usize>,
+    text: String,
+    parts: Vec<StringPart>,
+    quoted: bool,
+    bare: bool,
+    escaped: bool,
+}
+
+impl Word {
+    fn begin(&mut self, offset: usize) {
+        self.start.get_or_insert(offset);
+    }
+
+    fn push_part(&mut self, part: StringPart) {
+        if !self.text.is_empty() {
+            self.parts
+                .push(StringPart::Literal(std::mem::take(&mut self.text)));
+        }
+        self.parts.push(part);
+    }
+
+    fn into_token(mut self, source: &str, span: Span) -> Token {
+        if !self.parts.is_empty() {
+            if !self.text.is_empty() {
+                self.parts.push(StringPart::Literal(self.text));
+            }
+            return Token {
+                value: source.to_string(),
+                kind: TokenKind::InterpolatedString(self.parts),
+                span,
+            };
+        }
+        // Quoting or escaping never makes a word an operator, number or boolean
+        let kind = match (self.quoted, self.bare, self.escaped) {
+            (false, _, false) => return Token::new(&self.text).with_span(span),
+            (true, false, false) => TokenKind::String,
+            _ => TokenKind::Word,
+        };
+        Token {
+            value: self.text,
+            kind,
+            span,
+        }
+    }
+}
+
+// A heredoc whose body starts on the line after its `<<`
+struct PendingHeredoc {
+    start: usize,
+    index: usize,
+    delimiter: String,
+    quoted: bool,
+    strip_tabs: bool,
+}
+
+struct Lexer<'a> {
+    line: &'a str,
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    // Where `line` starts in the original source, for the spans of nested lexers
+    offset: usize,
+}
+
+pub fn lex(line: &str) -> Result<Vec<Token>, ShellError> {
+    let mut tokens = Lexer::new(line, 0).lex().map_err(|e| match e {
+        ShellError::Lex { message, span } => ShellError::Lex {
+            message,
+            span: span.locate(line),
+        },
+        e => e,
+    })?;
+    locate(&mut tokens, line);
+    Ok(tokens)
 }
 
-pub fn lex(line: &str) -> Result<Vec<Token>, String> {
-    let mut tokens = Vec::new();
+// Fills in the line numbers of the tokens, including those of command substitutions
+fn locate(tokens: &mut [Token], source: &str) {
+    for token in tokens {
+        token.span = token.span.locate(source);
+        if let TokenKind::InterpolatedString(parts) = &mut token.kind {
+            for part in parts {
+                if let StringPart::Command(tokens) = part {
+                    locate(tokens, source);
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Lexer<'a> {
+    fn new(line: &'a str, offset: usize) -> Self {
+        Lexer {
+            line,
+            chars: line.char_indices().peekable(),
+            offset,
+        }
+    }
+
+    fn position(&mut self) -> usize {
+        self.chars.peek().map_or(self.line.len(), |(i, _)| *i)
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        Span::new(self.offset + start, self.offset + end)
+    }
+
+    // Errors span from `start` to the end of the input
+    fn error(&self, message: &str, start: usize) -> ShellError {
+        ShellError::Lex {
+            message: message.to_string(),
+            span: self.span(start, self.line.len()),
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        self.chars.next().map(|(_, c)| c)
+    }
 
-    let mut token = String::new();
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, c)| *c)
+    }
+
+    fn next_if(&mut self, f: impl Fn(char) -> bool) -> Option<char> {
+        self.chars.next_if(|(_, c)| f(*c)).map(|(_, c)| c)
+    }
 
-    let mut iter = line.chars().peekable();
-    while let Some(c) = iter.next() {
-        match c {
-            '"' | '\'' => {
-                if let Some(value) = lex_string(&mut token, &mut tokens, c, &mut iter) {
-                    return value;
+    fn lex(&mut self) -> Result<Vec<Token>, ShellError> {
+        let mut tokens = Vec::new();
+        let mut word = Word::default();
+        let mut heredocs = Vec::new();
+
+        while let Some((i, c)) = self.chars.next() {
+            match c {
+                '"' | '\'' => {
+                    word.begin(i);
+                    word.quoted = true;
+                    self.lex_string(c, i, &mut word)?;
+                }
+                '$' if self.peek() == Some('\'') => {
+                    self.next();
+                    word.begin(i);
+                    word.quoted = true;
+                    self.lex_ansi_c_string(i, &mut word)?;
+                }
+                '<' if self.peek() == Some('<') => {
+                    self.next();
+                    self.push_word(&mut tokens, &mut word, i);
+                    if self.next_if(|c| c == '<').is_some() {
+                        let span = self.span(i, i + 3);
+                        tokens.push(Token::new("<<<").with_span(span));
+                    } else {
+                        let strip_tabs = self.next_if(|c| c == '-').is_some();
+                        let operator = if strip_tabs { "<<-" } else { "<<" };
+                        let span = self.span(i, i + operator.len());
+                        tokens.push(Token::new(operator).with_span(span));
+                        let (delimiter, quoted) = self.lex_delimiter(i)?;
+                        heredocs.push(PendingHeredoc {
+                            start: i,
+                            index: tokens.len(),
+                            delimiter,
+                            quoted,
+                            strip_tabs,
+                        });
+                        // Replaced by the body once the end of the line is reached
+                        tokens.push(Token::new(""));
+                    }
+                }
+                '&' | '|' if self.peek() == Some(c) => {
+                    self.next();
+                    self.push_word(&mut tokens, &mut word, i);
+                    let operator = if c == '&' { "&&" } else { "||" };
+                    tokens.push(Token::new(operator).with_span(self.span(i, i + 2)));
+                }
+                '>' | '<' | '|' | ';' | '&' | '(' | ')' => {
+                    self.push_word(&mut tokens, &mut word, i);
+                    tokens.push(Token::new(&c.to_string()).with_span(self.span(i, i + 1)));
+                }
+                // Only an unquoted `~` or `~/` starting a word is the home directory
+                '~' if word.start.is_none()
+                    && matches!(
+                        self.peek(),
+                        None | Some('/' | ' ' | '\t' | '\r' | '\n' | ';' | '|' | '&' | ')')
+                    ) =>
+                {
+                    word.begin(i);
+                    word.text += &std::env::var("HOME").unwrap_or_else(|_| c.to_string());
+                    word.bare = true;
+                }
+                // A `#` starting a word comments out the rest of the line
+                '#' if word.start.is_none() => while self.next_if(|c| c != '\n').is_some() {},
+                '\\' => match self.next() {
+                    Some('\n') => (),
+                    Some(c) => {
+                        word.begin(i);
+                        word.text.push(c);
+                        word.escaped = true;
+                    }
+                    None => {
+                        word.begin(i);
+                        word.text.push('\\');
+                        word.bare = true;
+                    }
+                },
+                ' ' | '\t' | '\r' => self.push_word(&mut tokens, &mut word, i),
+                '\n' => {
+                    self.push_word(&mut tokens, &mut word, i);
+                    tokens.push(Token::new("\n").with_span(self.span(i, i + 1)));
+                    for heredoc in heredocs.drain(..) {
+                        tokens[heredoc.index] = self.lex_heredoc(&heredoc)?;
+                    }
+                }
+                _ => {
+                    word.begin(i);
+                    word.text.push(c);
+                    word.bare = true;
                 }
             }
-            '>' | '|' | ';' => {
-                if !token.is_empty() {
-                    tokens.push(Token::new(&token));
-                    token.clear();
+        }
+
+        self.push_word(&mut tokens, &mut word, self.line.len());
+
+        if let Some(heredoc) = heredocs.first() {
+            return Err(self.error(UNTERMINATED_HEREDOC, heredoc.start));
+        }
+
+        Ok(tokens)
+    }
+
+    // Quoting any part of the delimiter, e.g. `<<'EOF'`, turns off expansion in the body
+    fn lex_delimiter(&mut self, start: usize) -> Result<(String, bool), ShellError> {
+        while self.next_if(|c| c == ' ' || c == '\t').is_some() {}
+
+        let mut delimiter = String::new();
+        let mut quoted = false;
+        let mut quote = None;
+        while let Some(c) = self.peek() {
+            match (quote, c) {
+                (None, ' ' | '\t' | '\n' | ';' | '|' | '&' | '>' | '<' | '(' | ')') => break,
+                (None, '"' | '\'') => {
+                    quote = Some(c);
+                    quoted = true;
                 }
-                tokens.push(Token::new(&c.to_string()));
+                (Some(q), c) if c == q => quote = None,
+                _ => delimiter.push(c),
             }
-            ' ' => {
-                if !token.is_empty() {
-                    tokens.push(Token::new(&token));
-                    token.clear();
+            self.next();
+        }
+
+        match (quote, delimiter.is_empty()) {
+            (Some(_), _) => Err(self.error(UNTERMINATED_STRING, start)),
+            (None, true) => Err(self.error("Syntax Error: Expected a delimiter after `<<`", start)),
+            (None, false) => Ok((delimiter, quoted)),
+        }
+    }
+
+    // Reads lines up to the delimiter, expanding `$` like a double-quoted string unless quoted
+    fn lex_heredoc(&mut self, heredoc: &PendingHeredoc) -> Result<Token, ShellError> {
+        let start = self.position();
+        let mut body = String::new();
+        loop {
+            let mut line = String::new();
+            let terminated = loop {
+                match self.next() {
+                    Some('\n') => break true,
+                    Some(c) => line.push(c),
+                    None => break false,
                 }
+            };
+            let line = match heredoc.strip_tabs {
+                true => line.trim_start_matches('\t'),
+                false => &line,
+            };
+            if line == heredoc.delimiter {
+                break;
             }
-            _ => token.push(c),
+            if !terminated {
+                return Err(self.error(UNTERMINATED_HEREDOC, heredoc.start));
+            }
+            body.push_str(line);
+            body.push('\n');
+        }
+
+        let end = self.position();
+        let span = self.span(start, end);
+        if heredoc.quoted {
+            return Ok(Token {
+                value: body,
+                kind: TokenKind::String,
+                span,
+            });
         }
+
+        let mut lexer = Lexer::new(&body, span.start);
+        let mut word = Word {
+            start: Some(0),
+            quoted: true,
+            ..Word::default()
+        };
+        while let Some(c) = lexer.next() {
+            match c {
+                '\\' => match lexer.next() {
+                    Some('\n') => (),
+                    Some(c @ ('\\' | '$' | '`')) => word.text.push(c),
+                    Some(c) => {
+                        word.text.push('\\');
+                        word.text.push(c);
+                    }
+                    None => word.text.push('\\'),
+                },
+                '$' => match lexer.lex_expansion()? {
+                    Some(part) => word.push_part(part),
+                    None => word.text.push('$'),
+                },
+                c => word.text.push(c),
+            }
+        }
+        Ok(word.into_token(&body, span))
     }
 
-    if !token.is_empty() {
-        tokens.push(Token::new(&token));
+    fn push_word(&self, tokens: &mut Vec<Token>, word: &mut Word, end: usize) {
+        if let Some(start) = word.start {
+            let span = self.span(start, end);
+            tokens.push(std::mem::take(word).into_token(&self.line[start..end], span));
+        }
     }
 
-    Ok(tokens)
-}
+    // Single quotes are fully literal, double quotes allow a few escapes and `$` expansions
+    fn lex_string(&mut self, quote: char, start: usize, word: &mut Word) -> Result<(), ShellError> {
+        while let Some(c) = self.next() {
+            match c {
+                c if c == quote => return Ok(()),
+                '\\' if quote == '"' => match self.next() {
+                    Some('n') => word.text.push('\n'),
+                    Some('t') => word.text.push('\t'),
+                    Some('\n') => (),
+                    Some(c @ ('"' | '\\' | '$' | '`')) => word.text.push(c),
+                    Some(c) => {
+                        word.text.push('\\');
+                        word.text.push(c);
+                    }
+                    None => break,
+                },
+                '$' if quote == '"' => match self.lex_expansion()? {
+                    Some(part) => word.push_part(part),
+                    None => word.text.push('$'),
+                },
+                c => word.text.push(c),
+            }
+        }
+
+        Err(self.error(UNTERMINATED_STRING, start))
+    }
+
+    // Lexes `$name`, `${name}` or `$(command)` after the `$`
+    fn lex_expansion(&mut self) -> Result<Option<StringPart>, ShellError> {
+        let start = self.position() - 1;
+        match self.peek() {
+            Some('(') => {
+                self.next();
+                let (offset, command) = self.lex_substitution(start)?;
+                let tokens = Lexer::new(command, self.offset + offset).lex()?;
+                Ok(Some(StringPart::Command(tokens)))
+            }
+            Some('{') => {
+                self.next();
+                let mut name = String::new();
+                loop {
+                    match self.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(self.error(UNTERMINATED_STRING, start)),
+                    }
+                }
+                Ok(Some(StringPart::Variable(name)))
+            }
+            Some(c) if c.is_ascii_alphanumeric() || c == '_' => {
+                let mut name = String::new();
+                while let Some(c) = self.next_if(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    name.push(c);
+                }
+                Ok(Some(StringPart::Variable(name)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    // Reads up to the `)` closing a `$(`, skipping over nested parentheses and strings
+    fn lex_substitution(&mut self, dollar: usize) -> Result<(usize, &'a str), ShellError> {
+        let line = self.line;
+        let start = self.position();
+        let mut depth = 1;
+        let mut quote = None;
+
+        while let Some((i, c)) = self.chars.next() {
+            match (quote, c) {
+                (Some('"') | None, '\\') => {
+                    self.next();
+                }
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => (),
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '(') => depth += 1,
+                (None, ')') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok((start, &line[start..i]));
+                    }
+                }
+                (None, _) => (),
+            }
+        }
+
+        Err(self.error(UNTERMINATED_STRING, dollar))
+    }
 
-fn lex_string(
-    token: &mut String,
-    tokens: &mut Vec<Token>,
-    c: char,
-    iter: &mut std::iter::Peekable<std::str::Chars<'_>>,
-) -> Option<Result<Vec<Token>, String>> {
-    if !token.is_empty() {
-        tokens.push(Token::new(&*token));
-        token.clear();
-    }
-
-    let mut current = '\0';
-    let string_start = c;
-    token.push(c);
-
-    while let Some(c) = iter.next() {
-        current = c;
-        token.push(c);
-        if c == string_start {
-            tokens.push(Token::new(&*token));
-            token.clear();
-            break;
+    // `$'...'` strings understand the ANSI-C escapes, e.g. `\n`, `\x41` and `\u{1F600}`
+    fn lex_ansi_c_string(&mut self, start: usize, word: &mut Word) -> Result<(), ShellError> {
+        while let Some((escape, c)) = self.chars.next() {
+            match c {
+                '\'' => return Ok(()),
+                '\\' => match self.next() {
+                    Some('n') => word.text.push('\n'),
+                    Some('t') => word.text.push('\t'),
+                    Some('r') => word.text.push('\r'),
+                    Some('a') => word.text.push('\x07'),
+                    Some('b') => word.text.push('\x08'),
+                    Some('e') => word.text.push('\x1b'),
+                    Some('f') => word.text.push('\x0c'),
+                    Some('v') => word.text.push('\x0b'),
+                    Some('0') => word.text.push('\0'),
+                    Some(c @ ('\\' | '\'' | '"')) => word.text.push(c),
+                    Some('x') => match self.lex_hex(2).and_then(char::from_u32) {
+                        Some(c) => word.text.push(c),
+                        None => word.text.push_str("\\x"),
+                    },
+                    Some('u') => {
+                        let braced = self.next_if(|c| c == '{').is_some();
+                        let code = self.lex_hex(if braced { 6 } else { 4 });
+                        let message = if braced && self.next_if(|c| c == '}').is_none() {
+                            "Syntax Error: Unterminated unicode escape"
+                        } else {
+                            match code.and_then(char::from_u32) {
+                                Some(c) => {
+                                    word.text.push(c);
+                                    continue;
+                                }
+                                None => "Syntax Error: Invalid unicode escape",
+                            }
+                        };
+                        let end = self.position();
+                        return Err(ShellError::Lex {
+                            message: message.to_string(),
+                            span: self.span(escape, end),
+                        });
+                    }
+                    Some(c) => {
+                        word.text.push('\\');
+                        word.text.push(c);
+                    }
+                    None => break,
+                },
+                c => word.text.push(c),
+            }
         }
+
+        Err(self.error(UNTERMINATED_STRING, start))
     }
 
-    if current != string_start {
-        return Some(Err("Syntax Error: Unterminated string".to_string()));
+    fn lex_hex(&mut self, max_digits: usize) -> Option<u32> {
+        let mut digits = String::new();
+        while digits.len() < max_digits {
+            match self.next_if(|c| c.is_ascii_hexdigit()) {
+                Some(c) => digits.push(c),
+                None => break,
+            }
+        }
+        u32::from_str_radix(&digits, 16).ok()
     }
-    None
 }
 
 #[cfg(test)]
@@ -149,11 +665,13 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "Hello, World!".to_string(),
-                    kind: TokenKind::String
+                    kind: TokenKind::String,
+                    span: Span::default(),
                 }
             ]
         );
@@ -161,7 +679,7 @@ mod tests {
 
     #[test]
     fn test_lexer_string_invalid() {
-        let tokens = lex("echo 'Hello, World!").unwrap_err();
+        let tokens = lex("echo 'Hello, World!").unwrap_err().to_string();
         assert_eq!(tokens, "Syntax Error: Unterminated string");
     }
 
@@ -173,11 +691,13 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "Hello, World!".to_string(),
-                    kind: TokenKind::String
+                    kind: TokenKind::String,
+                    span: Span::default(),
                 }
             ]
         );
@@ -185,19 +705,19 @@ mod tests {
 
     #[test]
     fn test_lexer_string_double_invalid() {
-        let tokens = lex("echo \"Hello, World!").unwrap_err();
+        let tokens = lex("echo \"Hello, World!").unwrap_err().to_string();
         assert_eq!(tokens, "Syntax Error: Unterminated string");
     }
 
     #[test]
     fn test_lexer_string_mixed() {
-        let tokens = lex("echo 'Hello, World!\"").unwrap_err();
+        let tokens = lex("echo 'Hello, World!\"").unwrap_err().to_string();
         assert_eq!(tokens, "Syntax Error: Unterminated string");
     }
 
     #[test]
     fn test_lexer_string_mixed_double() {
-        let tokens = lex("echo \"Hello, World!'").unwrap_err();
+        let tokens = lex("echo \"Hello, World!'").unwrap_err().to_string();
         assert_eq!(tokens, "Syntax Error: Unterminated string");
     }
 
@@ -209,11 +729,13 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "Hello, \"World\"!".to_string(),
-                    kind: TokenKind::String
+                    kind: TokenKind::String,
+                    span: Span::default(),
                 }
             ]
         );
@@ -227,11 +749,13 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "Hello, 'World'!".to_string(),
-                    kind: TokenKind::String
+                    kind: TokenKind::String,
+                    span: Span::default(),
                 }
             ]
         );
@@ -240,16 +764,257 @@ mod tests {
     #[test]
     fn test_lexer_string_no_space_before_string() {
         let tokens = lex("echo\"Hello, World!\"").unwrap();
+        assert_eq!(
+            tokens,
+            vec![Token {
+                value: "echoHello, World!".to_string(),
+                kind: TokenKind::Word,
+                span: Span::default(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lexer_concatenation() {
+        let tokens =
+            lex("--name=\"my file\" foo\"bar\"baz 'a'\"b\"$'\\t' \"1\"2 --user=\"$USER\"").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token {
+                    value: "--name=my file".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "foobarbaz".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "ab\t".to_string(),
+                    kind: TokenKind::String,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "12".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "--user=\"$USER\"".to_string(),
+                    kind: TokenKind::InterpolatedString(vec![
+                        StringPart::Literal("--user=".to_string()),
+                        StringPart::Variable("USER".to_string())
+                    ]),
+                    span: Span::default(),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_spans() {
+        let line = "ls | --name=\"a b\" <<<x";
+        let spans = lex(line)
+            .unwrap()
+            .iter()
+            .map(|token| token.span)
+            .collect::<Vec<Span>>();
+        assert_eq!(
+            spans,
+            [(0, 2), (3, 4), (5, 17), (18, 21), (21, 22)]
+                .iter()
+                .map(|(start, end)| Span::new(*start, *end).locate(line))
+                .collect::<Vec<Span>>()
+        );
+    }
+
+    #[test]
+    fn test_lexer_newlines() {
+        let tokens = lex("ls\n\npwd |\\\n wc\n").unwrap();
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| (t.kind.clone(), t.span.line))
+                .collect::<Vec<(TokenKind, usize)>>(),
+            vec![
+                (TokenKind::Word, 1),
+                (TokenKind::Newline, 1),
+                (TokenKind::Newline, 2),
+                (TokenKind::Word, 3),
+                (TokenKind::Pipe, 3),
+                (TokenKind::Word, 4),
+                (TokenKind::Newline, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_tabs() {
+        let tokens = lex("\tls\t-a\t# note\r\n").unwrap();
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| t.value.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["ls", "-a", "\n"]
+        );
+    }
+
+    #[test]
+    fn test_lexer_groups() {
+        let tokens = lex("(cd /tmp)|{ ls; } '{' a{b}").unwrap();
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| t.kind.clone())
+                .collect::<Vec<TokenKind>>(),
+            vec![
+                TokenKind::LeftParen,
+                TokenKind::Word,
+                TokenKind::Word,
+                TokenKind::RightParen,
+                TokenKind::Pipe,
+                TokenKind::LeftBrace,
+                TokenKind::Word,
+                TokenKind::SemiColon,
+                TokenKind::RightBrace,
+                TokenKind::String,
+                TokenKind::Word
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_logical_operators() {
+        let tokens = lex("a&&b || c|d & e&").unwrap();
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| (t.kind.clone(), t.value.as_str()))
+                .collect::<Vec<(TokenKind, &str)>>(),
+            vec![
+                (TokenKind::Word, "a"),
+                (TokenKind::And, "&&"),
+                (TokenKind::Word, "b"),
+                (TokenKind::Or, "||"),
+                (TokenKind::Word, "c"),
+                (TokenKind::Pipe, "|"),
+                (TokenKind::Word, "d"),
+                (TokenKind::Ampersand, "&"),
+                (TokenKind::Word, "e"),
+                (TokenKind::Ampersand, "&")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_input_redirection() {
+        let tokens = lex("grep a<in <<<x").unwrap();
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| (t.kind.clone(), t.value.as_str()))
+                .collect::<Vec<(TokenKind, &str)>>(),
+            vec![
+                (TokenKind::Word, "grep"),
+                (TokenKind::Word, "a"),
+                (TokenKind::LessThan, "<"),
+                (TokenKind::Word, "in"),
+                (TokenKind::HereString, "<<<"),
+                (TokenKind::Word, "x")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_heredoc() {
+        let tokens =
+            lex("cat <<EOF > out; cat <<-'END'\nhi $USER\nEOF\n\t\t$HOME\n\tEND\npwd").unwrap();
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| t.kind.clone())
+                .collect::<Vec<TokenKind>>(),
+            vec![
+                TokenKind::Word,
+                TokenKind::Heredoc,
+                TokenKind::InterpolatedString(vec![
+                    StringPart::Literal("hi ".to_string()),
+                    StringPart::Variable("USER".to_string()),
+                    StringPart::Literal("\n".to_string())
+                ]),
+                TokenKind::GreaterThan,
+                TokenKind::Word,
+                TokenKind::SemiColon,
+                TokenKind::Word,
+                TokenKind::Heredoc,
+                TokenKind::String,
+                TokenKind::Newline,
+                TokenKind::Word
+            ]
+        );
+        assert_eq!(tokens[8].value, "$HOME\n");
+        assert_eq!(tokens[10].value, "pwd");
+    }
+
+    #[test]
+    fn test_lexer_heredoc_invalid() {
+        assert_eq!(
+            lex("cat <<EOF\nhi").unwrap_err(),
+            ShellError::Lex {
+                message: UNTERMINATED_HEREDOC.to_string(),
+                span: Span::new(4, 12).locate("cat <<EOF\nhi")
+            }
+        );
+        assert_eq!(
+            lex("cat <<EOF").unwrap_err().to_string(),
+            UNTERMINATED_HEREDOC
+        );
+        assert!(lex("cat <<").is_err());
+    }
+
+    #[test]
+    fn test_lexer_here_string() {
+        let tokens = lex("cat <<< 'a b'").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token {
+                    value: "cat".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "<<<".to_string(),
+                    kind: TokenKind::HereString,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "a b".to_string(),
+                    kind: TokenKind::String,
+                    span: Span::default(),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_empty_string() {
+        let tokens = lex("echo ''").unwrap();
         assert_eq!(
             tokens,
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
-                    value: "Hello, World!".to_string(),
-                    kind: TokenKind::String
+                    value: "".to_string(),
+                    kind: TokenKind::String,
+                    span: Span::default(),
                 }
             ]
         );
@@ -263,11 +1028,13 @@ mod tests {
             vec![
                 Token {
                     value: "true".to_string(),
-                    kind: TokenKind::Boolean
+                    kind: TokenKind::Boolean,
+                    span: Span::default(),
                 },
                 Token {
                     value: "false".to_string(),
-                    kind: TokenKind::Boolean
+                    kind: TokenKind::Boolean,
+                    span: Span::default(),
                 }
             ]
         );
@@ -281,15 +1048,18 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "Hello,".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "World!".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 }
             ]
         );
@@ -303,15 +1073,18 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "Hello,".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "World!".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 }
             ]
         );
@@ -325,15 +1098,18 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: ">".to_string(),
-                    kind: TokenKind::GreaterThan
+                    kind: TokenKind::GreaterThan,
+                    span: Span::default(),
                 },
                 Token {
                     value: "file.txt".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 }
             ]
         );
@@ -347,15 +1123,18 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: ">".to_string(),
-                    kind: TokenKind::GreaterThan
+                    kind: TokenKind::GreaterThan,
+                    span: Span::default(),
                 },
                 Token {
                     value: "file.txt".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 }
             ]
         );
@@ -369,15 +1148,18 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: ">".to_string(),
-                    kind: TokenKind::GreaterThan
+                    kind: TokenKind::GreaterThan,
+                    span: Span::default(),
                 },
                 Token {
                     value: "file.txt".to_string(),
-                    kind: TokenKind::String
+                    kind: TokenKind::String,
+                    span: Span::default(),
                 }
             ]
         );
@@ -391,15 +1173,18 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: ">".to_string(),
-                    kind: TokenKind::GreaterThan
+                    kind: TokenKind::GreaterThan,
+                    span: Span::default(),
                 },
                 Token {
                     value: "file.txt".to_string(),
-                    kind: TokenKind::String
+                    kind: TokenKind::String,
+                    span: Span::default(),
                 }
             ]
         );
@@ -413,15 +1198,18 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: ">".to_string(),
-                    kind: TokenKind::GreaterThan
+                    kind: TokenKind::GreaterThan,
+                    span: Span::default(),
                 },
                 Token {
                     value: "file.txt".to_string(),
-                    kind: TokenKind::String
+                    kind: TokenKind::String,
+                    span: Span::default(),
                 }
             ]
         );
@@ -435,15 +1223,18 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: ">".to_string(),
-                    kind: TokenKind::GreaterThan
+                    kind: TokenKind::GreaterThan,
+                    span: Span::default(),
                 },
                 Token {
                     value: "file.txt".to_string(),
-                    kind: TokenKind::String
+                    kind: TokenKind::String,
+                    span: Span::default(),
                 }
             ]
         );
@@ -451,7 +1242,7 @@ mod tests {
 
     #[test]
     fn test_lexer_redirection_with_string_mixed() {
-        let tokens = lex("echo > 'file.txt\"").unwrap_err();
+        let tokens = lex("echo > 'file.txt\"").unwrap_err().to_string();
         assert_eq!(tokens, "Syntax Error: Unterminated string");
     }
 
@@ -463,15 +1254,18 @@ mod tests {
             vec![
                 Token {
                     value: "Hello, World!".to_string(),
-                    kind: TokenKind::String
+                    kind: TokenKind::String,
+                    span: Span::default(),
                 },
                 Token {
                     value: ">".to_string(),
-                    kind: TokenKind::GreaterThan
+                    kind: TokenKind::GreaterThan,
+                    span: Span::default(),
                 },
                 Token {
                     value: "file.txt".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 }
             ]
         );
@@ -485,27 +1279,33 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "Hello,".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "World!".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "|".to_string(),
-                    kind: TokenKind::Pipe
+                    kind: TokenKind::Pipe,
+                    span: Span::default(),
                 },
                 Token {
                     value: "wc".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "-l".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 }
             ]
         );
@@ -519,27 +1319,33 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "Hello,".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "World!".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: ";".to_string(),
-                    kind: TokenKind::SemiColon
+                    kind: TokenKind::SemiColon,
+                    span: Span::default(),
                 },
                 Token {
                     value: "wc".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "-l".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 }
             ]
         );
@@ -553,36 +1359,306 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "Hello,".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "World!".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: ";".to_string(),
-                    kind: TokenKind::SemiColon
+                    kind: TokenKind::SemiColon,
+                    span: Span::default(),
                 },
                 Token {
                     value: ";".to_string(),
-                    kind: TokenKind::SemiColon
+                    kind: TokenKind::SemiColon,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "wc".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "-l".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_newline() {
+        let tokens = lex("echo Hello, |\nwc \\\n-l").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token {
+                    value: "echo".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "Hello,".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "|".to_string(),
+                    kind: TokenKind::Pipe,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "\n".to_string(),
+                    kind: TokenKind::Newline,
+                    span: Span::default(),
                 },
                 Token {
                     value: "wc".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "-l".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_string_multiline() {
+        let tokens = lex("echo 'Hello,\nWorld!'").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token {
+                    value: "echo".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "Hello,\nWorld!".to_string(),
+                    kind: TokenKind::String,
+                    span: Span::default(),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_shebang() {
+        assert_eq!(lex("#!/usr/bin/env hug_shell").unwrap(), vec![]);
+        assert_eq!(lex("  # ls").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_lexer_home() {
+        let home = std::env::var("HOME").unwrap();
+        let values = lex("~ ~/src '~' \"a~b\" a~ ~b")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.value)
+            .collect::<Vec<String>>();
+        assert_eq!(
+            values,
+            vec![
+                home.clone(),
+                format!("{}/src", home),
+                "~".to_string(),
+                "a~b".to_string(),
+                "a~".to_string(),
+                "~b".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_comment() {
+        let tokens = lex("ls # list files\npwd#not a comment '#'").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token {
+                    value: "ls".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "\n".to_string(),
+                    kind: TokenKind::Newline,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "pwd#not".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "a".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "comment".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "#".to_string(),
+                    kind: TokenKind::String,
+                    span: Span::default(),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_escapes() {
+        let tokens = lex("echo \"say \\\"hi\\\"\\n\\t\\d\" 'a\\nb' a\\ b\\>c \\12").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token {
+                    value: "echo".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "say \"hi\"\n\t\\d".to_string(),
+                    kind: TokenKind::String,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "a\\nb".to_string(),
+                    kind: TokenKind::String,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "a b>c".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "12".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_escaped_quote_invalid() {
+        let tokens = lex("echo \"Hello, World!\\\"").unwrap_err().to_string();
+        assert_eq!(tokens, "Syntax Error: Unterminated string");
+    }
+
+    #[test]
+    fn test_lexer_ansi_c_string() {
+        let tokens = lex("$'a\\tb\\n\\x41\\u{1F600}\\u00e9\\''").unwrap();
+        assert_eq!(
+            tokens,
+            vec![Token {
+                value: "a\tb\nA\u{1F600}\u{e9}'".to_string(),
+                kind: TokenKind::String,
+                span: Span::default(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lexer_ansi_c_string_invalid() {
+        assert_eq!(
+            lex("$'\\u{110000}'").unwrap_err().to_string(),
+            "Syntax Error: Invalid unicode escape"
+        );
+        assert_eq!(
+            lex("$'\\u{41'").unwrap_err().to_string(),
+            "Syntax Error: Unterminated unicode escape"
+        );
+        assert_eq!(
+            lex("$'abc\\'").unwrap_err().to_string(),
+            UNTERMINATED_STRING
+        );
+    }
+
+    #[test]
+    fn test_lexer_interpolated_string() {
+        let tokens = lex("echo \"hi $name, ${HOME}! $(pwd | cat \")\") $\" 'a $b'").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token {
+                    value: "echo".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                },
+                Token {
+                    value: "\"hi $name, ${HOME}! $(pwd | cat \")\") $\"".to_string(),
+                    kind: TokenKind::InterpolatedString(vec![
+                        StringPart::Literal("hi ".to_string()),
+                        StringPart::Variable("name".to_string()),
+                        StringPart::Literal(", ".to_string()),
+                        StringPart::Variable("HOME".to_string()),
+                        StringPart::Literal("! ".to_string()),
+                        StringPart::Command(vec![
+                            Token {
+                                value: "pwd".to_string(),
+                                kind: TokenKind::Word,
+                                span: Span::default(),
+                            },
+                            Token {
+                                value: "|".to_string(),
+                                kind: TokenKind::Pipe,
+                                span: Span::default(),
+                            },
+                            Token {
+                                value: "cat".to_string(),
+                                kind: TokenKind::Word,
+                                span: Span::default(),
+                            },
+                            Token {
+                                value: ")".to_string(),
+                                kind: TokenKind::String,
+                                span: Span::default(),
+                            }
+                        ]),
+                        StringPart::Literal(" $".to_string()),
+                    ]),
+                    span: Span::default(),
+                },
+                Token {
+                    value: "a $b".to_string(),
+                    kind: TokenKind::String,
+                    span: Span::default(),
                 }
             ]
         );
     }
 
+    #[test]
+    fn test_lexer_interpolated_string_invalid() {
+        assert_eq!(
+            lex("echo \"$(pwd\"").unwrap_err().to_string(),
+            UNTERMINATED_STRING
+        );
+        assert_eq!(
+            lex("echo \"${HOME\"").unwrap_err().to_string(),
+            UNTERMINATED_STRING
+        );
+    }
+
     #[test]
     fn test_lexer_integer() {
         let tokens = lex("echo 123").unwrap();
@@ -591,11 +1667,13 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "123".to_string(),
-                    kind: TokenKind::Integer
+                    kind: TokenKind::Integer,
+                    span: Span::default(),
                 }
             ]
         );
@@ -609,11 +1687,13 @@ mod tests {
             vec![
                 Token {
                     value: "echo".to_string(),
-                    kind: TokenKind::Word
+                    kind: TokenKind::Word,
+                    span: Span::default(),
                 },
                 Token {
                     value: "123.456".to_string(),
-                    kind: TokenKind::Float
+                    kind: TokenKind::Float,
+                    span: Span::default(),
                 }
             ]
         );
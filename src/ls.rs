@@ -1,12 +1,13 @@
-use crate::builtin::{handle_builtin_error, BuiltinExitCode};
+use crate::builtin::BuiltinExitCode;
 use crate::command::Command;
+use crate::error::ShellError;
 use crate::typesystem::Type;
 
 impl Command {
     fn list_dir(&self, path: &str) -> Type {
         match std::fs::read_dir(path) {
             Ok(entries) => self.list_dir_files(entries),
-            Err(e) => handle_builtin_error(e),
+            Err(e) => ShellError::from(e).into(),
         }
     }
 
@@ -18,7 +19,7 @@ impl Command {
                     path: entry.path(),
                     full_path: false,
                 }),
-                Err(e) => return handle_builtin_error(e),
+                Err(e) => return ShellError::from(e).into(),
             }
         }
         Type::Array(files)
@@ -28,11 +29,15 @@ impl Command {
         let args = self.get_args();
         match args.len() {
             0 => self.list_dir("."),
-            1 => self.list_dir(&args[0].run_as_arg()),
-            _ => Type::Error {
-                message: "Too many arguments".into(),
-                code: BuiltinExitCode::TooManyArguments as i32,
+            1 => match args[0].run_as_arg() {
+                Ok(path) => self.list_dir(&path),
+                Err(e) => e,
             },
+            _ => ShellError::Runtime {
+                message: "Too many arguments".into(),
+                code: BuiltinExitCode::TooManyArguments,
+            }
+            .into(),
         }
     }
 }
@@ -67,6 +72,7 @@ mod tests {
                 args: Vec::new(),
             },
             stdin: None,
+            redirects: Vec::new(),
         }
         .run_ls();
         match output {
@@ -128,7 +134,7 @@ mod tests {
         })
         .run();
         match output {
-            Type::Error { code, message } => {
+            Type::Error { code, message, .. } => {
                 assert_eq!(code, BuiltinExitCode::FileNotFound as i32);
                 assert_eq!(message, "No such file or directory (os error 2)");
             }
@@ -147,7 +153,7 @@ mod tests {
         })
         .run();
         match output {
-            Type::Error { code, message } => {
+            Type::Error { code, message, .. } => {
                 assert_eq!(code, BuiltinExitCode::TooManyArguments as i32);
                 assert_eq!(message, "Too many arguments");
             }
@@ -1,36 +1,129 @@
-use rustyline;
+use rustyline::history::DefaultHistory;
+use rustyline::Editor;
+use std::io::{IsTerminal, Read};
+use std::sync::{Arc, Mutex};
 
+use builtin::BuiltinExitCode;
+use error::ShellError;
+
+mod background;
 mod command;
+mod conditional;
+mod error;
+mod field;
+mod git;
+mod group;
+mod helper;
+mod highlighter;
+mod hinter;
+mod history;
+mod interpolation;
 mod lexer;
 mod parser;
 mod pipes;
+mod prompt;
 mod redirect;
+mod script;
+mod serialize;
+mod span;
 mod typesystem;
+mod validator;
 
 mod builtin;
 mod external;
 
 // Built-in commands
 mod cd;
+mod complete;
+mod exit;
+mod from;
 mod ls;
+mod open;
 mod pwd;
+mod save;
+mod source;
+mod to;
 
 fn main() -> rustyline::Result<()> {
+    exit::add_exit_hook(background::kill_jobs);
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let output = match args.first().map(String::as_str) {
+        None if std::io::stdin().is_terminal() => return run_interactive(),
+        None => {
+            let mut source = String::new();
+            match std::io::stdin().read_to_string(&mut source) {
+                Ok(_) => script::run_script(&source),
+                Err(e) => ShellError::from(e).into(),
+            }
+        }
+        Some("-c") => match args.get(1) {
+            Some(command) => script::run_script(command),
+            None => ShellError::Runtime {
+                message: "-c requires a command".into(),
+                code: BuiltinExitCode::TooFewArguments,
+            }
+            .into(),
+        },
+        Some("--check") => match args.get(1) {
+            Some(path) => script::check_file(std::path::Path::new(path)),
+            None => ShellError::Runtime {
+                message: "--check requires a file".into(),
+                code: BuiltinExitCode::TooFewArguments,
+            }
+            .into(),
+        },
+        Some(option) if option.starts_with('-') => ShellError::Runtime {
+            message: format!("Unknown option `{}`", option),
+            code: BuiltinExitCode::SyntaxError,
+        }
+        .into(),
+        Some(path) => script::run_file(std::path::Path::new(path)),
+    };
+
+    script::print_output(&output);
+    exit::exit(output.exit_code());
+}
+
+fn run_interactive() -> rustyline::Result<()> {
+    let history = history::History::load(history::history_path());
+    let mut rl = Editor::<helper::ShellHelper, DefaultHistory>::new()?;
+    for entry in history.entries() {
+        rl.add_history_entry(entry.line.as_str())?;
+    }
+
+    let history = Arc::new(Mutex::new(history));
+    let saved_history = history.clone();
+    exit::add_exit_hook(move || {
+        if let Ok(history) = saved_history.lock() {
+            if let Err(e) = history.compact() {
+                eprintln!("Error: Could not compact history: {}", e);
+            }
+        }
+    });
+    rl.set_helper(Some(helper::ShellHelper::new(history.clone())));
+
+    let mut state = prompt::PromptState::default();
+    if let Some(path) = script::rc_path() {
+        let output = script::run_file(&path);
+        script::print_output(&output);
+        state.status = output.exit_code();
+    }
+
     loop {
-        let mut rl = rustyline::DefaultEditor::new()?;
-        let readline = rl.readline(&format!(
-            "{} >> ",
-            std::env::current_dir()
-                .unwrap()
-                .to_string_lossy()
-                .replace(std::env::var("HOME").unwrap().as_str(), "~")
-        ));
-        let tokens = match readline {
-            Ok(line) => lexer::lex(&line.replace("~", std::env::var("HOME").unwrap().as_str())),
-            Err(e) => match e {
-                rustyline::error::ReadlineError::Eof => {
-                    std::process::exit(0);
+        background::reap_jobs();
+        let readline = rl.readline(&prompt::render(&prompt::PromptConfig::from_env(), &state));
+        let line = match readline {
+            Ok(line) => {
+                rl.add_history_entry(line.as_str())?;
+                if let (Ok(mut history), Ok(cwd)) = (history.lock(), std::env::current_dir()) {
+                    if let Err(e) = history.push(&line, cwd) {
+                        eprintln!("Error: Could not save history: {}", e);
+                    }
                 }
+                line
+            }
+            Err(e) => match e {
+                rustyline::error::ReadlineError::Eof => exit::exit(0),
                 rustyline::error::ReadlineError::Interrupted => {
                     continue;
                 }
@@ -41,20 +134,10 @@ fn main() -> rustyline::Result<()> {
             },
         };
 
-        let mut commands = match tokens {
-            Ok(tokens) => parser::Parser::new(tokens.iter().peekable()).parse(),
-            Err(e) => {
-                eprintln!("{}", e);
-                continue;
-            }
-        };
-
-        for command in commands.iter_mut() {
-            let output = command.run();
-            match output {
-                typesystem::Type::Null => (),
-                _ => println!("{}", output),
-            }
-        }
+        let start = std::time::Instant::now();
+        let output = script::run_line(&line);
+        script::print_output(&output);
+        state.status = output.exit_code();
+        state.duration = Some(start.elapsed());
     }
 }
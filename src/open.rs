@@ -0,0 +1,125 @@
+use std::path::{Path, PathBuf};
+
+use crate::builtin::BuiltinExitCode;
+use crate::command::Command;
+use crate::error::ShellError;
+use crate::from::InputFormat;
+use crate::typesystem::Type;
+
+impl Command {
+    // .json, .csv, .tsv, .toml and .yaml files are parsed, anything else is read as a string,
+    // or as binary if it isn't UTF-8
+    pub fn run_open(&self) -> Type {
+        let args = self.get_args();
+        let path = match args.as_slice() {
+            [] => {
+                return ShellError::Runtime {
+                    message: "Missing file to open".into(),
+                    code: BuiltinExitCode::TooFewArguments,
+                }
+                .into()
+            }
+            [path] => match path.run_as_arg() {
+                Ok(path) => PathBuf::from(path),
+                Err(e) => return e,
+            },
+            _ => {
+                return ShellError::Runtime {
+                    message: "Too many arguments".into(),
+                    code: BuiltinExitCode::TooManyArguments,
+                }
+                .into()
+            }
+        };
+
+        let contents = match read(&path) {
+            Ok(Type::String(contents)) => contents,
+            Ok(binary) => return binary,
+            Err(e) => return e.into(),
+        };
+        match InputFormat::from_extension(&path) {
+            Some(format) => match format.parse(&contents) {
+                Ok(value) => value,
+                Err(e) => e.into(),
+            },
+            None => Type::String(contents),
+        }
+    }
+}
+
+// A file's contents as a string, or as binary if it isn't UTF-8
+pub fn read(path: &Path) -> Result<Type, ShellError> {
+    match String::from_utf8(std::fs::read(path)?) {
+        Ok(contents) => Ok(Type::String(contents)),
+        Err(e) => Ok(Type::Binary(e.into_bytes())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::script::run_line;
+
+    #[test]
+    fn test_run() {
+        let dir = std::env::temp_dir();
+        let record = Type::Record(vec![
+            ("name".to_string(), Type::String("hug".to_string())),
+            ("size".to_string(), Type::Integer(3)),
+        ]);
+        for extension in ["json", "toml", "yaml"] {
+            let path = dir.join(format!("hug_shell_test_open.{}", extension));
+            let _ = std::fs::remove_file(&path);
+            let line = format!(
+                "echo '{{\"name\": \"hug\", \"size\": 3}}' | from json | save {}",
+                path.display()
+            );
+            assert_eq!(run_line(&line), Type::Null);
+
+            assert_eq!(run_line(&format!("open {}", path.display())), record);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_text() {
+        let path = std::env::temp_dir().join("hug_shell_test_open.txt");
+        std::fs::write(&path, "{not json}\n").unwrap();
+        assert_eq!(
+            run_line(&format!("open {}", path.display())),
+            Type::String("{not json}\n".to_string())
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_run_binary() {
+        let path = std::env::temp_dir().join("hug_shell_test_open.png");
+        std::fs::write(&path, [0x89, b'P', b'N', b'G', 0xff]).unwrap();
+        let output = run_line(&format!("open {}", path.display()));
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(output, Type::Binary(vec![0x89, b'P', b'N', b'G', 0xff]));
+        assert_eq!(
+            output.to_colorless_string(),
+            format!(
+                "00000000: 8950 4e47 ff{}.PNG.\n(5 bytes of binary data)",
+                " ".repeat(29)
+            )
+        );
+    }
+
+    #[test]
+    fn test_run_invalid() {
+        assert_eq!(
+            run_line("open hug_shell_test_open_missing.json").exit_code(),
+            BuiltinExitCode::FileNotFound as i32
+        );
+
+        let path = std::env::temp_dir().join("hug_shell_test_open_invalid.json");
+        std::fs::write(&path, "{\"a\": ").unwrap();
+        let output = run_line(&format!("open {}", path.display()));
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(output.exit_code(), BuiltinExitCode::ParseError as i32);
+    }
+}
@@ -1,220 +1,496 @@
 use crate::builtin::BuiltinExt;
 use crate::command::{Command, CommandKind};
-use crate::lexer::{Token, TokenKind};
+use crate::error::ShellError;
+use crate::interpolation::Interpolation;
+use crate::lexer::{StringPart, Token, TokenKind};
+use crate::redirect::Redirect;
+use crate::span::Span;
 
 pub struct Parser<'a> {
     tokens: std::iter::Peekable<std::slice::Iter<'a, Token>>,
+    // The span of the last consumed token other than a newline, errors at the end of input point
+    // just past it, on its line
+    last: Span,
+    // Open `{` blocks, outside of them a `}` is an ordinary argument
+    braces: usize,
+    // A group was still open at the end of input
+    unclosed: bool,
 }
 impl<'a> Parser<'a> {
     pub fn new(tokens: std::iter::Peekable<std::slice::Iter<'a, Token>>) -> Self {
-        Parser { tokens }
+        Parser {
+            tokens,
+            last: Span::default(),
+            braces: 0,
+            unclosed: false,
+        }
+    }
+
+    // The line editor asks for more lines while a `{` block or `(` subshell is open
+    pub fn is_unclosed(&self) -> bool {
+        self.unclosed
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.next()?;
+        if token.kind != TokenKind::Newline {
+            self.last = token.span;
+        }
+        Some(token)
+    }
+
+    // Points at the next token, or just past the last one at the end of input
+    fn error(&mut self, expected: &str) -> Command {
+        let (found, span) = match self.tokens.peek() {
+            // The end of a line reads like the end of input
+            Some(token) if token.kind == TokenKind::Newline => (
+                String::new(),
+                Span {
+                    end: token.span.start,
+                    ..token.span
+                },
+            ),
+            Some(token) => (format!(", found `{}`", token.value), token.span),
+            None => (
+                String::new(),
+                Span {
+                    start: self.last.end,
+                    ..self.last
+                },
+            ),
+        };
+        Command::new(CommandKind::Error(ShellError::Parse {
+            message: format!("Syntax Error: Expected {}{}", expected, found),
+            span,
+        }))
     }
 
     pub fn parse(&mut self) -> Vec<Command> {
+        self.parse_until(None)
+    }
+
+    // Statements end at a `;`, `&` or newline. Those with a syntax error become
+    // `CommandKind::Error` and parsing resumes after the next one
+    fn parse_until(&mut self, closer: Option<&TokenKind>) -> Vec<Command> {
         let mut commands = Vec::new();
 
+        self.skip_newlines();
         while let Some(t) = self.tokens.peek() {
-            if commands.len() > 0 {
+            if Some(&t.kind) == closer {
+                break;
+            }
+            if !commands.is_empty() {
                 match t.kind {
-                    TokenKind::SemiColon => {
-                        self.tokens.next();
-                        if self.tokens.peek().is_none() {
-                            break;
+                    TokenKind::SemiColon | TokenKind::Ampersand | TokenKind::Newline => {
+                        if t.kind == TokenKind::Ampersand {
+                            background(&mut commands);
+                        }
+                        self.next();
+                        self.skip_newlines();
+                        match self.tokens.peek() {
+                            None => break,
+                            Some(t) if Some(&t.kind) == closer => break,
+                            _ => (),
                         }
                     }
                     _ => {
-                        return vec![Command {
-                            kind: CommandKind::Error(format!("Expected `;`, found `{}`", t.value)),
-                            stdin: None,
-                        }]
+                        commands.push(self.error("`;`"));
+                        self.next();
+                        self.synchronize();
+                        continue;
                     }
                 }
             }
             let command = self.parse_statement();
-            match command.kind {
-                CommandKind::Error(_) => {
-                    commands.push(command);
-                    break;
-                }
-                _ => commands.push(command),
+            if let CommandKind::Error(_) = command.kind {
+                self.synchronize();
             }
+            commands.push(command);
         }
         commands
     }
 
+    fn synchronize(&mut self) {
+        while self
+            .tokens
+            .next_if(|token| {
+                !matches!(
+                    token.kind,
+                    TokenKind::SemiColon
+                        | TokenKind::Ampersand
+                        | TokenKind::Newline
+                        | TokenKind::RightParen
+                        | TokenKind::RightBrace
+                )
+            })
+            .is_some()
+        {}
+    }
+
+    fn skip_newlines(&mut self) {
+        while self
+            .tokens
+            .next_if(|token| token.kind == TokenKind::Newline)
+            .is_some()
+        {}
+    }
+
+    // From the loosest to the tightest binding:
+    //   list     = and_or ((`;` | `&` | newline) and_or)*
+    //   and_or   = pipeline ((`&&` | `||`) pipeline)*
+    //   pipeline = command (`|` command)*
+    //   command  = (simple command | `( list )` | `{ list }`) redirect*
+    //   redirect = (`>` | `<<` | `<<<`) argument
     fn parse_statement(&mut self) -> Command {
-        let mut command = self.parse_expression();
+        let command = self.parse_and_or();
+        match self.tokens.peek() {
+            _ if matches!(command.kind, CommandKind::Error(_)) => command,
+            None => command,
+            Some(token) => match token.kind {
+                TokenKind::SemiColon
+                | TokenKind::Ampersand
+                | TokenKind::Newline
+                | TokenKind::RightParen
+                | TokenKind::RightBrace => command,
+                _ => self.error("`;`"),
+            },
+        }
+    }
 
-        while let Some(token) = self.tokens.peek() {
-            command = match token.kind {
-                TokenKind::GreaterThan | TokenKind::Pipe => self.parse_binary(command),
-                TokenKind::SemiColon => {
-                    break;
-                }
-                _ => Command {
-                    kind: CommandKind::Error("Unexpected token".to_string()),
-                    stdin: None,
-                },
-            };
+    fn parse_and_or(&mut self) -> Command {
+        let mut command = self.parse_pipeline();
+        while let Some(operator) = self
+            .tokens
+            .next_if(|token| matches!(token.kind, TokenKind::And | TokenKind::Or))
+        {
+            self.last = operator.span;
+            if let CommandKind::Error(_) = command.kind {
+                break;
+            }
+            // Like a pipe, `&&` and `||` can continue on the next line
+            self.skip_newlines();
+            if let Some(error) = self.missing_operand(operator) {
+                return error;
+            }
+            let right = self.parse_pipeline();
+            if let CommandKind::Error(_) = right.kind {
+                return right;
+            }
+            let (left, right) = (Box::new(command), Box::new(right));
+            command = Command::new(match operator.kind {
+                TokenKind::And => CommandKind::And { left, right },
+                _ => CommandKind::Or { left, right },
+            });
         }
         command
     }
 
-    // Expression is a maximum of one binary operator
-    fn parse_expression(&mut self) -> Command {
+    fn parse_pipeline(&mut self) -> Command {
+        let mut command = self.parse_command();
+        while let Some(operator) = self.tokens.next_if(|token| token.kind == TokenKind::Pipe) {
+            self.last = operator.span;
+            if let CommandKind::Error(_) = command.kind {
+                break;
+            }
+            // A pipeline can continue on the next line
+            self.skip_newlines();
+            if let Some(error) = self.missing_operand(operator) {
+                return error;
+            }
+            let destination = self.parse_command();
+            if let CommandKind::Error(_) = destination.kind {
+                return destination;
+            }
+            command = Command::new(CommandKind::Pipe {
+                source: Box::new(command),
+                destination: Box::new(destination),
+            });
+        }
+        command
+    }
+
+    // Redirects belong to the command and can come before, between or after its arguments.
+    // A command can also be nothing but redirects, e.g. `> out` creates an empty file
+    fn parse_command(&mut self) -> Command {
+        let mut redirects = Vec::new();
+        if let Some(error) = self.parse_redirects(&mut redirects) {
+            return error;
+        }
+        let next = self.tokens.peek().map(|token| &token.kind);
+        let mut command = if !redirects.is_empty() && !is_arg(next) && !is_group(next) {
+            Command::new(CommandKind::None)
+        } else {
+            self.parse_expression(&mut redirects)
+        };
+        if let CommandKind::Error(_) = command.kind {
+            return command;
+        }
+        if let Some(error) = self.parse_redirects(&mut redirects) {
+            return error;
+        }
+        command.redirects = redirects;
+        command
+    }
+
+    // Returns the syntax error, if any
+    fn parse_redirects(&mut self, redirects: &mut Vec<Redirect>) -> Option<Command> {
+        while let Some(operator) = self.tokens.next_if(|token| {
+            matches!(
+                token.kind,
+                TokenKind::GreaterThan
+                    | TokenKind::LessThan
+                    | TokenKind::Heredoc
+                    | TokenKind::HereString
+            )
+        }) {
+            self.last = operator.span;
+            if let Some(error) = self.missing_operand(operator) {
+                return Some(error);
+            }
+            let operand = self.parse_arg().unwrap();
+            redirects.push(match operator.kind {
+                TokenKind::GreaterThan => Redirect::Output(operand),
+                TokenKind::LessThan => Redirect::Input(operand),
+                TokenKind::Heredoc => Redirect::Heredoc(operand),
+                _ => Redirect::HereString(operand),
+            });
+        }
+        None
+    }
+
+    // Redirects take an argument, the other operators a command
+    fn missing_operand(&mut self, operator: &Token) -> Option<Command> {
+        let next = self.tokens.peek().map(|token| &token.kind);
+        let (is_arg, is_group) = (is_arg(next), is_group(next));
+        let expected = match operator.kind {
+            TokenKind::GreaterThan => "a destination",
+            TokenKind::LessThan => "a source",
+            TokenKind::HereString => "a string",
+            TokenKind::Heredoc => "a heredoc body",
+            _ => "a command",
+        };
+        match operator.kind {
+            TokenKind::GreaterThan
+            | TokenKind::LessThan
+            | TokenKind::Heredoc
+            | TokenKind::HereString
+                if is_arg =>
+            {
+                None
+            }
+            TokenKind::Pipe | TokenKind::And | TokenKind::Or if is_arg || is_group => None,
+            _ => Some(self.error(&format!("{} after `{}`", expected, operator.value))),
+        }
+    }
+
+    // A simple command, a value or a group
+    fn parse_expression(&mut self, redirects: &mut Vec<Redirect>) -> Command {
         if let Some(token) = self.tokens.peek() {
             match token.kind {
-                TokenKind::Word => self.parse_word(),
+                TokenKind::Word => self.parse_word(redirects),
                 TokenKind::String => self.parse_string(),
+                TokenKind::InterpolatedString(_) => self.parse_interpolated_string(),
                 TokenKind::Boolean => self.parse_boolean(),
                 TokenKind::Integer => self.parse_integer(),
                 TokenKind::Float => self.parse_float(),
-                TokenKind::SemiColon => Command {
-                    kind: CommandKind::None,
-                    stdin: None,
-                },
-                _ => Command {
-                    kind: CommandKind::Error("Unexpected token".to_string()),
-                    stdin: None,
-                },
+                TokenKind::LeftParen => self.parse_group(TokenKind::RightParen),
+                TokenKind::LeftBrace => self.parse_group(TokenKind::RightBrace),
+                TokenKind::SemiColon => Command::new(CommandKind::None),
+                _ => self.error("a command"),
             }
         } else {
-            Command {
-                kind: CommandKind::Error("Unexpected end of input".to_string()),
-                stdin: None,
+            self.error("a command")
+        }
+    }
+
+    // A group containing a syntax error becomes the first one, so it is reported like any other
+    fn parse_group(&mut self, closer: TokenKind) -> Command {
+        self.next();
+        let block = closer == TokenKind::RightBrace;
+        if block {
+            self.braces += 1;
+        }
+        let commands = self.parse_until(Some(&closer));
+        if block {
+            self.braces -= 1;
+        }
+
+        let closed = match self.tokens.peek() {
+            Some(token) if token.kind == closer => self.next(),
+            _ => None,
+        };
+        if let Some(error) = commands
+            .iter()
+            .find(|command| matches!(command.kind, CommandKind::Error(_)))
+        {
+            return error.clone();
+        }
+        let closer = match closed {
+            Some(closer) => closer.span,
+            None => {
+                self.unclosed = self.tokens.peek().is_none();
+                return self.error(if block { "`}`" } else { "`)`" });
+            }
+        };
+        let group = Command::new(match block {
+            true => CommandKind::Block(commands),
+            false => CommandKind::Subshell(commands),
+        });
+
+        // A word right after the `)`, e.g. `(cmd).stdout`, accesses fields of the output
+        match self.tokens.peek() {
+            Some(token)
+                if token.kind == TokenKind::Word
+                    && token.value.starts_with('.')
+                    && token.span.start == closer.end =>
+            {
+                let fields = token.value[1..]
+                    .split('.')
+                    .map(String::from)
+                    .collect::<Vec<String>>();
+                if fields.iter().any(String::is_empty) {
+                    return self.error("a field name");
+                }
+                self.next();
+                Command::new(CommandKind::Field {
+                    target: Box::new(group),
+                    fields,
+                })
             }
+            _ => group,
         }
     }
 
-    fn parse_word(&mut self) -> Command {
-        let token = self.tokens.next().unwrap();
+    fn parse_word(&mut self, redirects: &mut Vec<Redirect>) -> Command {
+        let token = self.next().unwrap();
         let builtin = if token.value.is_builtin() {
             Some(token.value.get_builtin())
         } else {
             None
         };
 
-        let args = self.parse_args();
+        let mut args = self.parse_args();
+        while self.tokens.peek().is_some_and(|token| {
+            matches!(
+                token.kind,
+                TokenKind::GreaterThan
+                    | TokenKind::LessThan
+                    | TokenKind::Heredoc
+                    | TokenKind::HereString
+            )
+        }) {
+            if let Some(error) = self.parse_redirects(redirects) {
+                return error;
+            }
+            args.extend(self.parse_args());
+        }
 
         if let Some(builtin) = builtin {
-            Command {
-                kind: CommandKind::Builtin { builtin, args },
-                stdin: None,
-            }
+            Command::new(CommandKind::Builtin { builtin, args })
         } else {
-            Command {
-                kind: CommandKind::External {
-                    name: token.clone(),
-                    args,
-                },
-                stdin: None,
-            }
+            Command::new(CommandKind::External {
+                name: token.clone(),
+                args,
+            })
         }
     }
 
     fn parse_args(&mut self) -> Vec<Command> {
         let mut args = Vec::new();
-        while let Some(token) = self.tokens.peek() {
-            match token.kind {
-                TokenKind::Word => {
-                    args.push(Command {
-                        kind: CommandKind::String(token.value.clone()),
-                        stdin: None,
-                    });
-                    self.tokens.next();
-                }
-                TokenKind::String => {
-                    args.push(Command {
-                        kind: CommandKind::String(token.value.clone()),
-                        stdin: None,
-                    });
-                    self.tokens.next();
-                }
-                TokenKind::Boolean => {
-                    args.push(Command {
-                        kind: CommandKind::Boolean(token.value.parse().unwrap()),
-                        stdin: None,
-                    });
-                    self.tokens.next();
-                }
-                TokenKind::Integer => {
-                    args.push(Command {
-                        kind: CommandKind::Integer(token.value.parse().unwrap()),
-                        stdin: None,
-                    });
-                    self.tokens.next();
-                }
-                TokenKind::Float => {
-                    args.push(Command {
-                        kind: CommandKind::Float(token.value.parse().unwrap()),
-                        stdin: None,
-                    });
-                    self.tokens.next();
-                }
-                _ => break,
-            }
+        while let Some(arg) = self.parse_arg() {
+            args.push(arg);
         }
         args
     }
 
+    // Words are plain strings in argument position
+    fn parse_arg(&mut self) -> Option<Command> {
+        let token = self.tokens.peek()?;
+        let arg = match token.kind {
+            TokenKind::Word | TokenKind::String => {
+                Command::new(CommandKind::String(token.value.clone()))
+            }
+            TokenKind::InterpolatedString(_) => return Some(self.parse_interpolated_string()),
+            TokenKind::Boolean => Command::new(CommandKind::Boolean(token.value.parse().unwrap())),
+            TokenKind::Integer => Command::new(CommandKind::Integer(token.value.parse().unwrap())),
+            TokenKind::Float => Command::new(CommandKind::Float(token.value.parse().unwrap())),
+            TokenKind::LeftBrace => Command::new(CommandKind::String(token.value.clone())),
+            TokenKind::RightBrace if self.braces == 0 => {
+                Command::new(CommandKind::String(token.value.clone()))
+            }
+            _ => return None,
+        };
+        self.next();
+        Some(arg)
+    }
+
     fn parse_string(&mut self) -> Command {
-        let token = self.tokens.next().unwrap();
-        Command {
-            kind: CommandKind::String(token.value.clone()),
-            stdin: None,
-        }
+        let token = self.next().unwrap();
+        Command::new(CommandKind::String(token.value.clone()))
+    }
+
+    fn parse_interpolated_string(&mut self) -> Command {
+        let token = self.next().unwrap();
+        let parts = match &token.kind {
+            TokenKind::InterpolatedString(parts) => parts,
+            _ => unreachable!(),
+        };
+        Command::new(CommandKind::InterpolatedString(
+            parts
+                .iter()
+                .map(|part| match part {
+                    StringPart::Literal(s) => Interpolation::Literal(s.clone()),
+                    StringPart::Variable(name) => Interpolation::Variable(name.clone()),
+                    StringPart::Command(tokens) => {
+                        Interpolation::Command(Parser::new(tokens.iter().peekable()).parse())
+                    }
+                })
+                .collect(),
+        ))
     }
 
     fn parse_boolean(&mut self) -> Command {
-        let token = self.tokens.next().unwrap();
-        Command {
-            kind: CommandKind::Boolean(token.value.parse().unwrap()),
-            stdin: None,
-        }
+        let token = self.next().unwrap();
+        Command::new(CommandKind::Boolean(token.value.parse().unwrap()))
     }
 
     fn parse_integer(&mut self) -> Command {
-        let token = self.tokens.next().unwrap();
-        Command {
-            kind: CommandKind::Integer(token.value.parse().unwrap()),
-            stdin: None,
-        }
+        let token = self.next().unwrap();
+        Command::new(CommandKind::Integer(token.value.parse().unwrap()))
     }
 
     fn parse_float(&mut self) -> Command {
-        let token = self.tokens.next().unwrap();
-        Command {
-            kind: CommandKind::Float(token.value.parse().unwrap()),
-            stdin: None,
-        }
+        let token = self.next().unwrap();
+        Command::new(CommandKind::Float(token.value.parse().unwrap()))
     }
+}
 
-    fn parse_binary(&mut self, command: Command) -> Command {
-        match self.tokens.next() {
-            Some(token) => match token.kind {
-                TokenKind::GreaterThan => Command {
-                    kind: CommandKind::Redirect {
-                        source: Box::new(command),
-                        destination: Box::new(self.parse_expression()),
-                    },
-                    stdin: None,
-                },
-                TokenKind::Pipe => Command {
-                    kind: CommandKind::Pipe {
-                        source: Box::new(command),
-                        destination: Box::new(self.parse_expression()),
-                    },
-                    stdin: None,
-                },
-                _ => Command {
-                    kind: CommandKind::Error("Unexpected token".to_string()),
-                    stdin: None,
-                },
-            },
-            None => Command {
-                kind: CommandKind::Error("Unexpected end of input".to_string()),
-                stdin: None,
-            },
+fn is_arg(kind: Option<&TokenKind>) -> bool {
+    matches!(
+        kind,
+        Some(
+            TokenKind::Word
+                | TokenKind::String
+                | TokenKind::InterpolatedString(_)
+                | TokenKind::Boolean
+                | TokenKind::Integer
+                | TokenKind::Float
+        )
+    )
+}
+
+fn is_group(kind: Option<&TokenKind>) -> bool {
+    matches!(kind, Some(TokenKind::LeftParen | TokenKind::LeftBrace))
+}
+
+// Statements with a syntax error are never run, so they stay as they are
+fn background(commands: &mut [Command]) {
+    if let Some(command) = commands.last_mut() {
+        if !matches!(command.kind, CommandKind::Error(_)) {
+            let kind = std::mem::replace(&mut command.kind, CommandKind::None);
+            let mut inner = Command::new(kind);
+            inner.redirects = std::mem::take(&mut command.redirects);
+            command.kind = CommandKind::Background(Box::new(inner));
         }
     }
 }
@@ -226,9 +502,10 @@ mod tests {
 
     #[test]
     fn test_parse_cd() {
-        let tokens = vec![Token {
+        let tokens = [Token {
             value: "cd".to_string(),
             kind: TokenKind::Word,
+            span: Span::default(),
         }];
 
         let commands = Parser::new(tokens.iter().peekable()).parse();
@@ -246,14 +523,16 @@ mod tests {
 
     #[test]
     fn test_parse_cd_with_one_arg() {
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: "cd".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: "/home".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
         ];
 
@@ -275,18 +554,21 @@ mod tests {
 
     #[test]
     fn test_parse_cd_with_multiple_args() {
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: "cd".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: "/home".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: "arg2".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
         ];
 
@@ -311,9 +593,10 @@ mod tests {
 
     #[test]
     fn test_parse_exit() {
-        let tokens = vec![Token {
+        let tokens = [Token {
             value: "exit".to_string(),
             kind: TokenKind::Word,
+            span: Span::default(),
         }];
 
         let commands = Parser::new(tokens.iter().peekable()).parse();
@@ -331,14 +614,16 @@ mod tests {
 
     #[test]
     fn test_parse_exit_with_one_arg() {
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: "exit".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: "1".to_string(),
                 kind: TokenKind::Integer,
+                span: Span::default(),
             },
         ];
 
@@ -357,18 +642,21 @@ mod tests {
 
     #[test]
     fn test_parse_exit_with_multiple_args() {
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: "exit".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: "1".to_string(),
                 kind: TokenKind::Integer,
+                span: Span::default(),
             },
             Token {
                 value: "2".to_string(),
                 kind: TokenKind::Integer,
+                span: Span::default(),
             },
         ];
 
@@ -393,9 +681,10 @@ mod tests {
 
     #[test]
     fn test_parse_ls() {
-        let tokens = vec![Token {
+        let tokens = [Token {
             value: "ls".to_string(),
             kind: TokenKind::Word,
+            span: Span::default(),
         }];
 
         let commands = Parser::new(tokens.iter().peekable()).parse();
@@ -413,14 +702,16 @@ mod tests {
 
     #[test]
     fn test_parse_ls_with_one_arg() {
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: "ls".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: "arg1".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
         ];
 
@@ -442,18 +733,21 @@ mod tests {
 
     #[test]
     fn test_parse_ls_with_multiple_args() {
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: "ls".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: "arg1".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: "arg2".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
         ];
 
@@ -478,9 +772,10 @@ mod tests {
 
     #[test]
     fn test_parse_string() {
-        let tokens = vec![Token {
+        let tokens = [Token {
             value: "hello".to_string(),
             kind: TokenKind::String,
+            span: Span::default(),
         }];
 
         let commands = Parser::new(tokens.iter().peekable()).parse();
@@ -492,30 +787,65 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_interpolated_string() {
+        let tokens = [Token {
+            value: "in $(pwd)".to_string(),
+            kind: TokenKind::InterpolatedString(vec![
+                StringPart::Literal("in ".to_string()),
+                StringPart::Command(vec![Token {
+                    value: "pwd".to_string(),
+                    kind: TokenKind::Word,
+                    span: Span::default(),
+                }]),
+            ]),
+            span: Span::default(),
+        }];
+
+        let commands = Parser::new(tokens.iter().peekable()).parse();
+        match commands[0].kind {
+            CommandKind::InterpolatedString(ref parts) => {
+                assert_eq!(
+                    *parts,
+                    vec![
+                        Interpolation::Literal("in ".to_string()),
+                        Interpolation::Command(vec![Command::new(CommandKind::Builtin {
+                            builtin: Builtin::Pwd,
+                            args: Vec::new()
+                        })])
+                    ]
+                );
+            }
+            _ => panic!("Expected InterpolatedString"),
+        }
+    }
+
     #[test]
     fn test_parse_boolean() {
-        let tokens = vec![Token {
+        let tokens = [Token {
             value: "true".to_string(),
             kind: TokenKind::Boolean,
+            span: Span::default(),
         }];
 
         let commands = Parser::new(tokens.iter().peekable()).parse();
         match commands[0].kind {
             CommandKind::Boolean(value) => {
-                assert_eq!(value, true);
+                assert!(value);
             }
             _ => panic!("Expected Boolean"),
         }
 
-        let tokens = vec![Token {
+        let tokens = [Token {
             value: "false".to_string(),
             kind: TokenKind::Boolean,
+            span: Span::default(),
         }];
 
         let commands = Parser::new(tokens.iter().peekable()).parse();
         match commands[0].kind {
             CommandKind::Boolean(value) => {
-                assert_eq!(value, false);
+                assert!(!value);
             }
             _ => panic!("Expected Boolean"),
         }
@@ -523,9 +853,10 @@ mod tests {
 
     #[test]
     fn test_parse_external() {
-        let tokens = vec![Token {
+        let tokens = [Token {
             value: "helloworld".to_string(),
             kind: TokenKind::Word,
+            span: Span::default(),
         }];
 
         let commands = Parser::new(tokens.iter().peekable()).parse();
@@ -536,6 +867,7 @@ mod tests {
                     Token {
                         value: "helloworld".to_string(),
                         kind: TokenKind::Word,
+                        span: Span::default(),
                     }
                 );
                 assert_eq!(*args, Vec::<Command>::new());
@@ -543,18 +875,21 @@ mod tests {
             _ => panic!("Expected External"),
         }
 
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: "helloworld".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: "arg1".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: "arg2".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
         ];
 
@@ -566,6 +901,7 @@ mod tests {
                     Token {
                         value: "helloworld".to_string(),
                         kind: TokenKind::Word,
+                        span: Span::default(),
                     }
                 );
                 assert_eq!(
@@ -589,105 +925,90 @@ mod tests {
 
     #[test]
     fn test_parse_redirect() {
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: "ls".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: ">".to_string(),
                 kind: TokenKind::GreaterThan,
+                span: Span::default(),
             },
             Token {
                 value: "output.txt".to_string(),
                 kind: TokenKind::String,
+                span: Span::default(),
             },
         ];
 
         let commands = Parser::new(tokens.iter().peekable()).parse();
-        match commands[0].kind {
-            CommandKind::Redirect {
-                ref source,
-                ref destination,
-            } => {
-                assert_eq!(
-                    **source,
-                    Command {
-                        kind: CommandKind::Builtin {
-                            builtin: Builtin::Ls,
-                            args: Vec::new()
-                        },
-                        stdin: None,
-                    }
-                );
-                assert_eq!(
-                    **destination,
-                    Command {
-                        kind: CommandKind::String("output.txt".to_string()),
-                        stdin: None,
-                    }
-                );
+        assert_eq!(
+            commands[0],
+            Command {
+                kind: CommandKind::Builtin {
+                    builtin: Builtin::Ls,
+                    args: Vec::new()
+                },
+                stdin: None,
+                redirects: vec![Redirect::Output(Command::new(CommandKind::String(
+                    "output.txt".to_string()
+                )))],
             }
-            _ => panic!("Expected Redirect"),
-        }
+        );
     }
 
     #[test]
     fn test_parse_redirect_from_string() {
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: "ls".to_string(),
                 kind: TokenKind::String,
+                span: Span::default(),
             },
             Token {
                 value: ">".to_string(),
                 kind: TokenKind::GreaterThan,
+                span: Span::default(),
             },
             Token {
                 value: "output.txt".to_string(),
                 kind: TokenKind::String,
+                span: Span::default(),
             },
         ];
 
         let commands = Parser::new(tokens.iter().peekable()).parse();
-        match commands[0].kind {
-            CommandKind::Redirect {
-                ref source,
-                ref destination,
-            } => {
-                assert_eq!(
-                    **source,
-                    Command {
-                        kind: CommandKind::String("ls".to_string()),
-                        stdin: None,
-                    }
-                );
-                assert_eq!(
-                    **destination,
-                    Command {
-                        kind: CommandKind::String("output.txt".to_string()),
-                        stdin: None,
-                    }
-                );
+        assert_eq!(
+            commands[0],
+            Command {
+                kind: CommandKind::String("ls".to_string()),
+                stdin: None,
+                redirects: vec![Redirect::Output(Command::new(CommandKind::String(
+                    "output.txt".to_string()
+                )))],
             }
-            _ => panic!("Expected Redirect"),
-        }
+        );
     }
 
     #[test]
     fn test_parse_pipe() {
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: "ls".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: "|".to_string(),
                 kind: TokenKind::Pipe,
+                span: Span::default(),
             },
             Token {
                 value: "grep".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
         ];
 
@@ -705,6 +1026,7 @@ mod tests {
                             args: Vec::new()
                         },
                         stdin: None,
+                        redirects: Vec::new(),
                     }
                 );
                 assert_eq!(
@@ -713,11 +1035,13 @@ mod tests {
                         kind: CommandKind::External {
                             name: Token {
                                 value: "grep".to_string(),
-                                kind: TokenKind::Word
+                                kind: TokenKind::Word,
+                                span: Span::default(),
                             },
                             args: Vec::new()
                         },
                         stdin: None,
+                        redirects: Vec::new(),
                     }
                 );
             }
@@ -727,18 +1051,21 @@ mod tests {
 
     #[test]
     fn test_parse_pipe_from_string() {
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: "ls".to_string(),
                 kind: TokenKind::String,
+                span: Span::default(),
             },
             Token {
                 value: "|".to_string(),
                 kind: TokenKind::Pipe,
+                span: Span::default(),
             },
             Token {
                 value: "grep".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
         ];
 
@@ -753,6 +1080,7 @@ mod tests {
                     Command {
                         kind: CommandKind::String("ls".to_string()),
                         stdin: None,
+                        redirects: Vec::new(),
                     }
                 );
                 assert_eq!(
@@ -761,11 +1089,13 @@ mod tests {
                         kind: CommandKind::External {
                             name: Token {
                                 value: "grep".to_string(),
-                                kind: TokenKind::Word
+                                kind: TokenKind::Word,
+                                span: Span::default(),
                             },
                             args: Vec::new()
                         },
                         stdin: None,
+                        redirects: Vec::new(),
                     }
                 );
             }
@@ -773,20 +1103,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_here_string() {
+        let tokens = [
+            Token {
+                value: "cat".to_string(),
+                kind: TokenKind::Word,
+                span: Span::default(),
+            },
+            Token {
+                value: "<<<".to_string(),
+                kind: TokenKind::HereString,
+                span: Span::default(),
+            },
+            Token {
+                value: "text".to_string(),
+                kind: TokenKind::Word,
+                span: Span::default(),
+            },
+        ];
+
+        let commands = Parser::new(tokens.iter().peekable()).parse();
+        assert_eq!(
+            commands[0],
+            Command {
+                kind: CommandKind::External {
+                    name: tokens[0].clone(),
+                    args: Vec::new()
+                },
+                stdin: None,
+                redirects: vec![Redirect::HereString(Command::new(CommandKind::String(
+                    "text".to_string()
+                )))],
+            }
+        );
+    }
+
     #[test]
     fn test_parse_multiple_commands() {
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: "ls".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: ";".to_string(),
                 kind: TokenKind::SemiColon,
+                span: Span::default(),
             },
             Token {
                 value: "cd".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
         ];
 
@@ -818,26 +1187,31 @@ mod tests {
 
     #[test]
     fn test_parse_three_commands() {
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: "ls".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: ";".to_string(),
                 kind: TokenKind::SemiColon,
+                span: Span::default(),
             },
             Token {
                 value: "cd".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
             Token {
                 value: ";".to_string(),
                 kind: TokenKind::SemiColon,
+                span: Span::default(),
             },
             Token {
                 value: "pwd".to_string(),
                 kind: TokenKind::Word,
+                span: Span::default(),
             },
         ];
 
@@ -878,11 +1252,246 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_errors() {
+        let cases = [
+            (
+                "ls >",
+                "Syntax Error: Expected a destination after `>`",
+                4,
+                4,
+            ),
+            (
+                "ls >\n",
+                "Syntax Error: Expected a destination after `>`",
+                4,
+                4,
+            ),
+            (
+                "ls | ;",
+                "Syntax Error: Expected a command after `|`, found `;`",
+                5,
+                6,
+            ),
+            ("'a' b", "Syntax Error: Expected `;`, found `b`", 4, 5),
+        ];
+        for (line, message, start, end) in cases {
+            let tokens = crate::lexer::lex(line).unwrap();
+            let commands = Parser::new(tokens.iter().peekable()).parse();
+            assert_eq!(
+                commands.last().unwrap().kind,
+                CommandKind::Error(ShellError::Parse {
+                    message: message.to_string(),
+                    span: Span::new(start, end).locate(line)
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn parse_recovers_after_errors() {
+        let tokens = crate::lexer::lex("ls >; pwd | ; 'a' b c; pwd").unwrap();
+        let commands = Parser::new(tokens.iter().peekable()).parse();
+        let errors = commands
+            .iter()
+            .filter(|command| matches!(command.kind, CommandKind::Error(_)))
+            .count();
+        assert_eq!(commands.len(), 4);
+        assert_eq!(errors, 3);
+        assert_eq!(
+            commands[3].kind,
+            CommandKind::Builtin {
+                builtin: Builtin::Pwd,
+                args: Vec::new()
+            }
+        );
+    }
+
+    #[test]
+    fn parse_newlines() {
+        let tokens = crate::lexer::lex("\nls\n\n  pwd |\n\n wc; ls\n").unwrap();
+        let commands = Parser::new(tokens.iter().peekable()).parse();
+        assert_eq!(commands.len(), 3);
+        assert!(matches!(commands[1].kind, CommandKind::Pipe { .. }));
+
+        let tokens = crate::lexer::lex("ls\ncd >\npwd").unwrap();
+        let commands = Parser::new(tokens.iter().peekable()).parse();
+        assert_eq!(commands.len(), 3);
+        match &commands[1].kind {
+            CommandKind::Error(e) => assert_eq!(e.span().unwrap().line, 2),
+            kind => panic!("Expected Error, got {:?}", kind),
+        }
+    }
+
+    // Renders the tree of a statement, e.g. `(a > b) | c`, to pin down precedence
+    fn shape(command: &Command) -> String {
+        let kind = match &command.kind {
+            CommandKind::External { name, .. } => name.value.clone(),
+            CommandKind::Builtin { builtin, .. } => format!("{:?}", builtin).to_lowercase(),
+            CommandKind::String(s) => s.clone(),
+            CommandKind::Block(commands) => format!(
+                "{{ {} }}",
+                commands
+                    .iter()
+                    .map(shape)
+                    .collect::<Vec<String>>()
+                    .join("; ")
+            ),
+            CommandKind::Pipe {
+                source,
+                destination,
+            } => format!("({} | {})", shape(source), shape(destination)),
+            CommandKind::And { left, right } => format!("({} && {})", shape(left), shape(right)),
+            CommandKind::Or { left, right } => format!("({} || {})", shape(left), shape(right)),
+            CommandKind::Background(command) => format!("({} &)", shape(command)),
+            kind => format!("{:?}", kind),
+        };
+        if command.redirects.is_empty() {
+            return kind;
+        }
+        let redirects = command
+            .redirects
+            .iter()
+            .map(|redirect| match redirect {
+                Redirect::Output(path) => format!(" > {}", shape(path)),
+                Redirect::Input(path) => format!(" < {}", shape(path)),
+                Redirect::Heredoc(body) => format!(" << {}", shape(body)),
+                Redirect::HereString(body) => format!(" <<< {}", shape(body)),
+            })
+            .collect::<String>();
+        format!("({}{})", kind, redirects)
+    }
+
+    #[test]
+    fn parse_precedence() {
+        let cases = [
+            ("a > out.txt | wc", vec!["((a > out.txt) | wc)"]),
+            ("a > x > y", vec!["(a > x > y)"]),
+            ("> out echo hi", vec!["(echo > out)"]),
+            ("grep foo < in > out", vec!["(grep < in > out)"]),
+            ("a > x b <<< y c", vec!["(a > x <<< y)"]),
+            ("> out", vec!["(None > out)"]),
+            ("{ a; b } > out", vec!["({ a; b } > out)"]),
+            ("a | b > c", vec!["(a | (b > c))"]),
+            ("a | b | c", vec!["((a | b) | c)"]),
+            ("a | b && c || d", vec!["(((a | b) && c) || d)"]),
+            ("a || b && c", vec!["((a || b) && c)"]),
+            ("a &&\n b |\n c", vec!["(a && (b | c))"]),
+            ("a && b; c & d", vec!["(a && b)", "(c &)", "d"]),
+            ("a | b &", vec!["((a | b) &)"]),
+            ("a > out &", vec!["((a > out) &)"]),
+        ];
+        for (line, expected) in cases {
+            let tokens = crate::lexer::lex(line).unwrap();
+            let commands = Parser::new(tokens.iter().peekable()).parse();
+            assert_eq!(
+                commands.iter().map(shape).collect::<Vec<String>>(),
+                expected,
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn parse_logical_operator_errors() {
+        let cases = [
+            ("ls &&", "Syntax Error: Expected a command after `&&`"),
+            (
+                "ls || ;",
+                "Syntax Error: Expected a command after `||`, found `;`",
+            ),
+            ("&& ls", "Syntax Error: Expected a command, found `&&`"),
+            (
+                "ls | pwd > &",
+                "Syntax Error: Expected a destination after `>`, found `&`",
+            ),
+            (
+                "ls < ;",
+                "Syntax Error: Expected a source after `<`, found `;`",
+            ),
+            ("> out |", "Syntax Error: Expected a command after `|`"),
+        ];
+        for (line, message) in cases {
+            let tokens = crate::lexer::lex(line).unwrap();
+            let commands = Parser::new(tokens.iter().peekable()).parse();
+            match &commands[0].kind {
+                CommandKind::Error(e) => assert_eq!(e.to_string(), message),
+                kind => panic!("Expected Error, got {:?}", kind),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_groups() {
+        let tokens = crate::lexer::lex("(cd /tmp; ls) | { wc -l\n} > out; echo { }").unwrap();
+        let commands = Parser::new(tokens.iter().peekable()).parse();
+        assert_eq!(commands.len(), 2);
+        match &commands[0].kind {
+            CommandKind::Pipe {
+                source,
+                destination,
+            } => {
+                assert!(matches!(&source.kind, CommandKind::Subshell(c) if c.len() == 2));
+                assert!(matches!(&destination.kind, CommandKind::Block(c) if c.len() == 1));
+                assert_eq!(destination.redirects.len(), 1);
+            }
+            kind => panic!("Expected Pipe, got {:?}", kind),
+        }
+        assert_eq!(commands[1].get_args().len(), 2);
+    }
+
+    #[test]
+    fn parse_group_errors() {
+        let cases = [
+            ("(ls", "Syntax Error: Expected `)`"),
+            ("{ ls; pwd", "Syntax Error: Expected `}`"),
+            (
+                "(ls >)",
+                "Syntax Error: Expected a destination after `>`, found `)`",
+            ),
+            ("ls)", "Syntax Error: Expected `;`, found `)`"),
+        ];
+        for (line, message) in cases {
+            let tokens = crate::lexer::lex(line).unwrap();
+            let commands = Parser::new(tokens.iter().peekable()).parse();
+            match &commands.last().unwrap().kind {
+                CommandKind::Error(e) => assert_eq!(e.to_string(), message),
+                kind => panic!("Expected Error, got {:?}", kind),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_fields() {
+        let tokens = crate::lexer::lex("(ls).stdout.length; (ls) .stdout").unwrap();
+        let commands = Parser::new(tokens.iter().peekable()).parse();
+        match &commands[0].kind {
+            CommandKind::Field { target, fields } => {
+                assert!(matches!(target.kind, CommandKind::Subshell(_)));
+                assert_eq!(fields, &["stdout", "length"]);
+            }
+            kind => panic!("Expected Field, got {:?}", kind),
+        }
+        // Only a word right after the `)` is a field
+        assert!(matches!(commands[1].kind, CommandKind::Error(_)));
+
+        let tokens = crate::lexer::lex("(ls).stdout.").unwrap();
+        match &Parser::new(tokens.iter().peekable()).parse()[0].kind {
+            CommandKind::Error(e) => assert_eq!(
+                e.to_string(),
+                "Syntax Error: Expected a field name, found `.stdout.`"
+            ),
+            kind => panic!("Expected Error, got {:?}", kind),
+        }
+    }
+
     #[test]
     fn parse_only_semicolon() {
-        let tokens = vec![Token {
+        let tokens = [Token {
             value: ";".to_string(),
             kind: TokenKind::SemiColon,
+            span: Span::default(),
         }];
 
         let commands = Parser::new(tokens.iter().peekable()).parse();
@@ -895,14 +1504,16 @@ mod tests {
 
     #[test]
     fn parse_multiple_semicolons() {
-        let tokens = vec![
+        let tokens = [
             Token {
                 value: ";".to_string(),
                 kind: TokenKind::SemiColon,
+                span: Span::default(),
             },
             Token {
                 value: ";".to_string(),
                 kind: TokenKind::SemiColon,
+                span: Span::default(),
             },
         ];
 
@@ -920,9 +1531,10 @@ mod tests {
 
     #[test]
     fn parse_integer() {
-        let tokens = vec![Token {
+        let tokens = [Token {
             value: "123".to_string(),
             kind: TokenKind::Integer,
+            span: Span::default(),
         }];
 
         let commands = Parser::new(tokens.iter().peekable()).parse();
@@ -935,10 +1547,12 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn parse_float() {
-        let tokens = vec![Token {
+        let tokens = [Token {
             value: "3.14".to_string(),
             kind: TokenKind::Float,
+            span: Span::default(),
         }];
 
         let commands = Parser::new(tokens.iter().peekable()).parse();
@@ -8,8 +8,8 @@ impl Command {
                 source,
                 destination,
             } => {
-                let source_output = source.run();
-                destination.stdin = Some(source_output);
+                // The source's stderr was already shown, `complete` can still read it
+                destination.stdin = Some(source.run());
                 destination.run()
             }
             _ => unreachable!(),
@@ -22,6 +22,7 @@ mod tests {
     use super::*;
 
     use crate::lexer::{Token, TokenKind};
+    use crate::span::Span;
 
     #[test]
     fn test_run() {
@@ -31,22 +32,26 @@ mod tests {
                     name: Token {
                         value: "echo".to_string(),
                         kind: TokenKind::Word,
+                        span: Span::default(),
                     },
                     args: vec![Command::new(CommandKind::String(
                         "Hello, world!".to_string(),
                     ))],
                 },
                 stdin: None,
+                redirects: Vec::new(),
             }),
             destination: Box::new(Command {
                 kind: CommandKind::External {
                     name: Token {
                         value: "grep".to_string(),
                         kind: TokenKind::Word,
+                        span: Span::default(),
                     },
                     args: vec![Command::new(CommandKind::String("world".to_string()))],
                 },
                 stdin: None,
+                redirects: Vec::new(),
             }),
         })
         .run();
@@ -66,29 +71,35 @@ mod tests {
                     source: Box::new(Command {
                         kind: CommandKind::String("Hello, world!\n".into()),
                         stdin: None,
+                        redirects: Vec::new(),
                     }),
                     destination: Box::new(Command {
                         kind: CommandKind::External {
                             name: Token {
                                 value: "grep".into(),
                                 kind: TokenKind::Word,
+                                span: Span::default(),
                             },
                             args: vec![Command::new(CommandKind::String("world".into()))],
                         },
                         stdin: None,
+                        redirects: Vec::new(),
                     }),
                 },
                 stdin: None,
+                redirects: Vec::new(),
             }),
             destination: Box::new(Command {
                 kind: CommandKind::External {
                     name: Token {
                         value: "wc".into(),
                         kind: TokenKind::Word,
+                        span: Span::default(),
                     },
                     args: vec![],
                 },
                 stdin: None,
+                redirects: Vec::new(),
             }),
         })
         .run();
@@ -96,7 +107,7 @@ mod tests {
             Type::Output(output) => {
                 assert_eq!(
                     String::from_utf8(output.stdout).unwrap(),
-                    "      1       2      20\n"
+                    "      1       2      14\n"
                 );
             }
             _ => panic!("Expected Output, got {}", output),
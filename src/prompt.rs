@@ -0,0 +1,213 @@
+use colored::{Color, Colorize};
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::git::git_status;
+
+const DEFAULT_SEGMENTS: &str = "cwd,git,status,duration";
+const DURATION_THRESHOLD: Duration = Duration::from_secs(1);
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Segment {
+    UserHost,
+    Cwd,
+    Git,
+    Status,
+    Duration,
+}
+
+impl Segment {
+    fn from_name(name: &str) -> Option<Segment> {
+        match name {
+            "user_host" => Some(Segment::UserHost),
+            "cwd" => Some(Segment::Cwd),
+            "git" => Some(Segment::Git),
+            "status" => Some(Segment::Status),
+            "duration" => Some(Segment::Duration),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Segment::UserHost => "user_host",
+            Segment::Cwd => "cwd",
+            Segment::Git => "git",
+            Segment::Status => "status",
+            Segment::Duration => "duration",
+        }
+    }
+
+    fn default_color(&self) -> Color {
+        match self {
+            Segment::UserHost => Color::Green,
+            Segment::Cwd => Color::Blue,
+            Segment::Git => Color::BrightMagenta,
+            Segment::Status => Color::Red,
+            Segment::Duration => Color::Yellow,
+        }
+    }
+}
+
+// Configured through the environment:
+//   HUG_PROMPT_SEGMENTS="user_host,cwd,git,status,duration"
+//   HUG_PROMPT_<SEGMENT>_COLOR="bright blue"
+#[derive(Debug, PartialEq, Clone)]
+pub struct PromptConfig {
+    pub segments: Vec<(Segment, Color)>,
+}
+
+impl PromptConfig {
+    pub fn from_env() -> Self {
+        let segments = std::env::var("HUG_PROMPT_SEGMENTS")
+            .unwrap_or(DEFAULT_SEGMENTS.to_string())
+            .split(',')
+            .filter_map(|name| Segment::from_name(name.trim()))
+            .map(|segment| {
+                let color = std::env::var(format!(
+                    "HUG_PROMPT_{}_COLOR",
+                    segment.name().to_uppercase()
+                ))
+                .ok()
+                .and_then(|color| color.parse().ok())
+                .unwrap_or(segment.default_color());
+                (segment, color)
+            })
+            .collect();
+        PromptConfig { segments }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PromptState {
+    pub status: i32,
+    pub duration: Option<Duration>,
+}
+
+pub fn render(config: &PromptConfig, state: &PromptState) -> String {
+    let segments = config
+        .segments
+        .iter()
+        .filter_map(|(segment, color)| {
+            render_segment(segment, state).map(|text| text.color(*color).to_string())
+        })
+        .collect::<Vec<String>>();
+    match segments.is_empty() {
+        true => ">> ".to_string(),
+        false => format!("{} >> ", segments.join(" ")),
+    }
+}
+
+fn render_segment(segment: &Segment, state: &PromptState) -> Option<String> {
+    match segment {
+        Segment::UserHost => Some(format!("{}@{}", user()?, host()?)),
+        Segment::Cwd => Some(shorten_path(&std::env::current_dir().ok()?)),
+        Segment::Git => {
+            let status = git_status(&std::env::current_dir().ok()?)?;
+            Some(format!(
+                "({}{})",
+                status.branch,
+                match status.dirty {
+                    Some(true) => "*",
+                    Some(false) => "",
+                    None => "?",
+                }
+            ))
+        }
+        Segment::Status => match state.status {
+            0 => None,
+            code => Some(format!("[{}]", code)),
+        },
+        Segment::Duration => match state.duration {
+            Some(duration) if duration >= DURATION_THRESHOLD => Some(format_duration(duration)),
+            _ => None,
+        },
+    }
+}
+
+fn user() -> Option<String> {
+    std::env::var("USER").or(std::env::var("LOGNAME")).ok()
+}
+
+fn host() -> Option<String> {
+    match std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .or(std::fs::read_to_string("/etc/hostname"))
+    {
+        Ok(host) => Some(host.trim().to_string()),
+        Err(_) => std::env::var("HOSTNAME").ok(),
+    }
+}
+
+// Abbreviates every directory but the last to its first character, e.g. `~/p/hug_shell`
+fn shorten_path(path: &Path) -> String {
+    let path = match std::env::var("HOME").map(|home| path.strip_prefix(home)) {
+        Ok(Ok(relative)) if relative.as_os_str().is_empty() => "~".to_string(),
+        Ok(Ok(relative)) => format!("~/{}", relative.to_string_lossy()),
+        _ => path.to_string_lossy().to_string(),
+    };
+
+    let components = path.split('/').collect::<Vec<&str>>();
+    components
+        .iter()
+        .enumerate()
+        .map(|(i, component)| {
+            if i == components.len() - 1 || component.is_empty() || *component == "~" {
+                component.to_string()
+            } else {
+                let keep = if component.starts_with('.') { 2 } else { 1 };
+                component.chars().take(keep).collect()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 60 {
+        format!("{}m{}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shorten_path() {
+        assert_eq!(shorten_path(Path::new("/")), "/");
+        assert_eq!(shorten_path(Path::new("/usr/local/bin")), "/u/l/bin");
+        assert_eq!(shorten_path(Path::new("/home/.config/hug")), "/h/.c/hug");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1.5s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m5s");
+    }
+
+    #[test]
+    fn test_render_status_and_duration() {
+        let config = PromptConfig {
+            segments: vec![
+                (Segment::Status, Color::Red),
+                (Segment::Duration, Color::Yellow),
+            ],
+        };
+
+        assert_eq!(render(&config, &PromptState::default()), ">> ");
+        assert_eq!(
+            render(
+                &config,
+                &PromptState {
+                    status: 50,
+                    duration: Some(Duration::from_secs(2)),
+                }
+            ),
+            format!("{} {} >> ", "[50]".red(), "2.0s".yellow())
+        );
+    }
+}
@@ -1,22 +1,24 @@
-use crate::builtin::{handle_builtin_error, BuiltinExitCode};
+use crate::builtin::BuiltinExitCode;
 use crate::command::Command;
+use crate::error::ShellError;
 use crate::typesystem::Type;
 
 impl Command {
     pub fn run_pwd(&self) -> Type {
         let args = self.get_args();
         if !args.is_empty() {
-            return Type::Error {
+            return ShellError::Runtime {
                 message: "Too many arguments".into(),
-                code: BuiltinExitCode::TooManyArguments as i32,
-            };
+                code: BuiltinExitCode::TooManyArguments,
+            }
+            .into();
         }
         match std::env::current_dir() {
             Ok(path) => Type::File {
-                path: path.into(),
+                path,
                 full_path: true,
             },
-            Err(e) => handle_builtin_error(e),
+            Err(e) => ShellError::from(e).into(),
         }
     }
 }
@@ -49,6 +51,7 @@ mod tests {
                 args: vec![],
             },
             stdin: None,
+            redirects: Vec::new(),
         }
         .run();
         match output {
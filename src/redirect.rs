@@ -1,47 +1,202 @@
+use std::fs::File;
 use std::io::Write;
+use std::path::Path;
 
-use crate::command::{Command, CommandKind};
+use crate::command::Command;
+use crate::error::ShellError;
+use crate::open::read;
+use crate::serialize::Format;
 use crate::typesystem::Type;
 
+// Redirects are attributes of a command and can appear anywhere in it, e.g. `> out echo hi`
+#[derive(Debug, PartialEq, Clone)]
+pub enum Redirect {
+    // `> path`
+    Output(Command),
+    // `< path`
+    Input(Command),
+    // `<< DELIMITER`, holding the body
+    Heredoc(Command),
+    // `<<< string`
+    HereString(Command),
+}
+
 impl Command {
-    pub fn run_redirect(&mut self) -> Type {
-        match &mut self.kind {
-            CommandKind::Redirect {
-                source,
-                destination,
-            } => {
-                let source_output = source.run();
-                let mut file = std::fs::OpenOptions::new()
-                    .write(true)
-                    .create(true)
-                    .truncate(true)
-                    .open(destination.run().to_undecorated_string())
-                    .unwrap();
-                file.write_all(source_output.to_colorless_string().as_bytes())
-                    .unwrap();
+    pub fn run_redirects(&mut self) -> Type {
+        let mut redirects = std::mem::take(&mut self.redirects);
+        let output = self.run_with_redirects(&mut redirects);
+        self.redirects = redirects;
+        output
+    }
+
+    // The last input redirect sets stdin, and every `>` destination is created but only the
+    // last one is returned to get the output
+    pub fn apply_redirects(&mut self, redirects: &mut [Redirect]) -> Result<Option<File>, Type> {
+        let mut destination = None;
+        for redirect in redirects.iter_mut() {
+            match redirect {
+                Redirect::Output(path) => destination = Some(create(path)?),
+                Redirect::Input(path) => {
+                    let path = match path.run() {
+                        error @ Type::Error { .. } => return Err(error),
+                        path => path.to_undecorated_string(),
+                    };
+                    self.stdin = Some(read(Path::new(&path)).map_err(Type::from)?);
+                }
+                Redirect::Heredoc(body) => match body.run() {
+                    error @ Type::Error { .. } => return Err(error),
+                    body => self.stdin = Some(Type::String(body.to_undecorated_string())),
+                },
+                // A here-string ends with a newline
+                Redirect::HereString(body) => match body.run() {
+                    error @ Type::Error { .. } => return Err(error),
+                    body => self.stdin = Some(Type::String(body.to_undecorated_string() + "\n")),
+                },
             }
-            _ => unreachable!(),
         }
+        Ok(destination)
+    }
 
-        Type::Null
+    fn run_with_redirects(&mut self, redirects: &mut [Redirect]) -> Type {
+        let destination = match self.apply_redirects(redirects) {
+            Ok(destination) => destination,
+            Err(error) => return error,
+        };
+        let output = self.run();
+        let mut file = match destination {
+            Some(file) => file,
+            None => return output,
+        };
+        // Written like stdin of an external command, so strings aren't quoted
+        let contents = match output {
+            error @ Type::Error { .. } => return error,
+            output => match output.serialize(Format::from_env()) {
+                Ok(contents) => contents,
+                Err(e) => return e.into(),
+            },
+        };
+        match file.write_all(&contents) {
+            Ok(()) => Type::Null,
+            Err(e) => ShellError::from(e).into(),
+        }
     }
 }
 
+fn create(path: &mut Command) -> Result<File, Type> {
+    let path = match path.run() {
+        error @ Type::Error { .. } => return Err(error),
+        path => path.to_undecorated_string(),
+    };
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .map_err(|e| ShellError::from(e).into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::script::run_line;
+
+    use crate::command::CommandKind;
+
     #[test]
     fn test_run() {
-        let output = Command::new(CommandKind::Redirect {
-            source: Box::new(Command::new(CommandKind::String("Hello, world!".into()))),
-            destination: Box::new(Command::new(CommandKind::String("test.txt".into()))),
-        })
-        .run_redirect();
+        let mut command = Command::new(CommandKind::String("Hello, world!".into()));
+        command
+            .redirects
+            .push(Redirect::Output(Command::new(CommandKind::String(
+                "test.txt".into(),
+            ))));
+        let output = command.run();
 
         assert_eq!(output, Type::Null);
         let contents = std::fs::read_to_string("test.txt").unwrap();
-        assert_eq!(contents, "\"Hello, world!\"");
+        assert_eq!(contents, "Hello, world!");
         std::fs::remove_file("test.txt").unwrap();
     }
+
+    #[test]
+    fn test_run_invalid_destination() {
+        let mut command = Command::new(CommandKind::String("Hello, world!".into()));
+        command
+            .redirects
+            .push(Redirect::Output(Command::new(CommandKind::String(
+                "missing_dir/test.txt".into(),
+            ))));
+
+        assert_eq!(command.run().exit_code(), 50);
+    }
+
+    #[test]
+    fn test_run_anywhere() {
+        let dir = std::env::temp_dir();
+        let input = dir.join("hug_shell_test_redirect_in");
+        let first = dir.join("hug_shell_test_redirect_first");
+        let last = dir.join("hug_shell_test_redirect_last");
+        std::fs::write(&input, "a\nb\nfoo\n").unwrap();
+
+        let line = format!(
+            "> {} grep < {} foo > {}",
+            first.display(),
+            input.display(),
+            last.display()
+        );
+        assert_eq!(run_line(&line), Type::Null);
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "");
+        assert_eq!(std::fs::read_to_string(&last).unwrap(), "foo\n");
+
+        for path in [input, first, last] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_binary() {
+        let dir = std::env::temp_dir();
+        let input = dir.join("hug_shell_test_redirect_binary_in");
+        let output = dir.join("hug_shell_test_redirect_binary_out");
+        let bytes = (0..=255).collect::<Vec<u8>>();
+        std::fs::write(&input, &bytes).unwrap();
+
+        let line = format!("cat < {} > {}", input.display(), output.display());
+        assert_eq!(run_line(&line), Type::Null);
+        assert_eq!(std::fs::read(&output).unwrap(), bytes);
+
+        for path in [input, output] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_missing_input() {
+        assert_eq!(
+            run_line("wc -l < hug_shell_test_missing_input").exit_code(),
+            50
+        );
+    }
+
+    #[test]
+    fn test_run_heredoc() {
+        std::env::set_var("HUG_SHELL_TEST_HEREDOC", "World");
+        match run_line("wc -l <<EOF\nHello,\n$HUG_SHELL_TEST_HEREDOC!\nEOF") {
+            Type::Output(output) => {
+                assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), "2");
+            }
+            output => panic!("Expected Output, got {}", output),
+        }
+    }
+
+    #[test]
+    fn test_run_here_string() {
+        match run_line("wc -l <<< 'Hello, world!'") {
+            Type::Output(output) => {
+                assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), "1");
+            }
+            output => panic!("Expected Output, got {}", output),
+        }
+    }
 }
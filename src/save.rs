@@ -0,0 +1,197 @@
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+
+use crate::builtin::BuiltinExitCode;
+use crate::command::Command;
+use crate::error::ShellError;
+use crate::serialize::Format;
+use crate::typesystem::Type;
+
+impl Command {
+    // e.g. `ls | save files.json`, existing files are only written with `--append` or `--force`.
+    // Arguments after `--` are paths, even if they start with `--`
+    pub fn run_save(&self) -> Type {
+        let args = match self.run_args() {
+            Ok(args) => args,
+            Err(e) => return e,
+        };
+        let end = args
+            .iter()
+            .position(|arg| arg == "--")
+            .unwrap_or(args.len());
+        let (flags, mut paths): (Vec<&String>, Vec<&String>) =
+            args[..end].iter().partition(|arg| arg.starts_with("--"));
+        paths.extend(args.iter().skip(end + 1));
+
+        let (mut append, mut force) = (false, false);
+        for flag in flags {
+            match flag.as_str() {
+                "--append" => append = true,
+                "--force" => force = true,
+                flag => {
+                    return ShellError::Type {
+                        message: format!(
+                            "Unknown flag `{}`, expected `--append` or `--force`",
+                            flag
+                        ),
+                    }
+                    .into()
+                }
+            }
+        }
+        let path = match paths.as_slice() {
+            [] => {
+                return ShellError::Runtime {
+                    message: "Missing file to save to".into(),
+                    code: BuiltinExitCode::TooFewArguments,
+                }
+                .into()
+            }
+            [path] => PathBuf::from(path),
+            _ => {
+                return ShellError::Runtime {
+                    message: "Too many arguments".into(),
+                    code: BuiltinExitCode::TooManyArguments,
+                }
+                .into()
+            }
+        };
+
+        // Output and binary content are saved byte for byte, whatever the extension, so e.g.
+        // `curl` can download a `.json` file. Parse it with `from` first to convert it
+        let contents = match &self.stdin {
+            Some(error @ Type::Error { .. }) => return error.clone(),
+            Some(Type::Output(o)) => o.stdout.clone(),
+            Some(Type::Binary(b)) => b.clone(),
+            Some(Type::Null) | None => Vec::new(),
+            Some(value) => match value.to_format(Format::from_extension(&path), true) {
+                Ok(s) => s.into_bytes(),
+                Err(e) => return e.into(),
+            },
+        };
+
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .append(append)
+            .create(append || force)
+            .create_new(!append && !force)
+            .truncate(!append)
+            .open(&path);
+        let written = match file {
+            Ok(mut file) => file.write_all(&contents),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                return ShellError::Runtime {
+                    message: format!(
+                    "`{}` already exists, use `--force` to overwrite it or `--append` to add to it",
+                    path.display()
+                ),
+                    code: BuiltinExitCode::InvalidArgument,
+                }
+                .into()
+            }
+            Err(e) => Err(e),
+        };
+        match written {
+            Ok(()) => Type::Null,
+            Err(e) => ShellError::from(e).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::lexer::lex;
+    use crate::parser::Parser;
+
+    fn save(value: Type, args: &str) -> Type {
+        let tokens = lex(&format!("save {}", args)).unwrap();
+        let mut command = Parser::new(tokens.iter().peekable()).parse().remove(0);
+        command.stdin = Some(value);
+        command.run()
+    }
+
+    #[test]
+    fn test_run() {
+        let path = std::env::temp_dir().join("hug_shell_test_save.json");
+        let _ = std::fs::remove_file(&path);
+        let value = Type::Array(vec![Type::Integer(1), Type::String("a".to_string())]);
+
+        assert_eq!(save(value.clone(), &path.display().to_string()), Type::Null);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[\n  1,\n  \"a\"\n]\n"
+        );
+        assert_eq!(
+            save(value.clone(), &path.display().to_string()).exit_code(),
+            BuiltinExitCode::InvalidArgument as i32
+        );
+        assert_eq!(
+            save(Type::Integer(2), &format!("--force {}", path.display())),
+            Type::Null
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2\n");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_run_output_raw() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let path = std::env::temp_dir().join("hug_shell_test_save_raw.json");
+        let _ = std::fs::remove_file(&path);
+        let output = Type::Output(std::process::Output {
+            status: std::process::ExitStatus::from_raw(0),
+            stdout: b"not json\n".to_vec(),
+            stderr: Vec::new(),
+        });
+        assert_eq!(save(output, &path.display().to_string()), Type::Null);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "not json\n");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_run_end_of_flags() {
+        let path = "--hug_shell_test_save";
+        let _ = std::fs::remove_file(path);
+        assert_eq!(
+            save(Type::Integer(1), &format!("--force -- {}", path)),
+            Type::Null
+        );
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "1\n");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_run_append_text() {
+        let path = std::env::temp_dir().join("hug_shell_test_save.txt");
+        let _ = std::fs::remove_file(&path);
+        for line in ["first\n", "second\n"] {
+            let value = Type::String(line.to_string());
+            assert_eq!(
+                save(value, &format!("{} --append", path.display())),
+                Type::Null
+            );
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_run_invalid() {
+        assert_eq!(
+            save(Type::Null, "").exit_code(),
+            BuiltinExitCode::TooFewArguments as i32
+        );
+        assert_eq!(
+            save(Type::Null, "a b").exit_code(),
+            BuiltinExitCode::TooManyArguments as i32
+        );
+        assert_eq!(
+            save(Type::Integer(1), "hug_shell_test_save.toml").exit_code(),
+            BuiltinExitCode::InvalidArgument as i32
+        );
+        assert!(!std::path::Path::new("hug_shell_test_save.toml").exists());
+    }
+}
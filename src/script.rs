@@ -0,0 +1,221 @@
+use homedir::my_home;
+
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use crate::builtin::BuiltinExitCode;
+use crate::command::CommandKind;
+use crate::error::ShellError;
+use crate::lexer;
+use crate::parser::Parser;
+use crate::typesystem::{bytes_to_string, Type};
+
+// A command's stdout is printed to the shell's own, its stderr was already shown as it ran
+pub fn print_output(output: &Type) {
+    match output {
+        Type::Null => (),
+        Type::Output(o) => {
+            if !o.stdout.is_empty() || o.stderr.is_empty() {
+                print_bytes(&o.stdout);
+            }
+        }
+        Type::Binary(bytes) => print_bytes(bytes),
+        Type::Error { .. } => eprintln!("{}", output),
+        _ => println!("{}", output),
+    }
+}
+
+// Content that isn't UTF-8 is only shown as a hex dump on a terminal, elsewhere it's written
+// byte for byte
+fn print_bytes(bytes: &[u8]) {
+    match std::str::from_utf8(bytes) {
+        Err(_) if !std::io::stdout().is_terminal() => {
+            let _ = std::io::stdout().write_all(bytes);
+        }
+        _ => println!("{}", bytes_to_string(bytes)),
+    }
+}
+
+// Runs every command on the line up to the first syntax error, printing all outputs but the
+// last, which is returned
+pub fn run_line(line: &str) -> Type {
+    let tokens = match lexer::lex(line) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            return Type::from(e).with_source(line);
+        }
+    };
+
+    let mut output = Type::Null;
+    for command in Parser::new(tokens.iter().peekable()).parse().iter_mut() {
+        print_output(&output);
+        output = command.run().with_source(line);
+        if let CommandKind::Error(_) = command.kind {
+            break;
+        }
+    }
+    output
+}
+
+// Newlines separate statements, so a script runs like one long line
+pub fn run_script(source: &str) -> Type {
+    run_line(source)
+}
+
+// Finds the syntax errors of every statement without running anything, with their line numbers
+pub fn check_script(source: &str) -> Vec<(usize, Type)> {
+    let errors = match lexer::lex(source) {
+        Ok(tokens) => Parser::new(tokens.iter().peekable())
+            .parse()
+            .into_iter()
+            .filter_map(|command| match command.kind {
+                CommandKind::Error(e) => Some(e),
+                _ => None,
+            })
+            .collect(),
+        Err(e) => vec![e],
+    };
+    errors
+        .into_iter()
+        .map(|e| {
+            let line = e.span().map_or(1, |span| span.line);
+            (line, Type::from(e).with_source(source))
+        })
+        .collect()
+}
+
+pub fn check_file(path: &Path) -> Type {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => return ShellError::from(e).into(),
+    };
+    let errors = check_script(&source);
+    for (number, error) in &errors {
+        if let Type::Error { message, .. } = error {
+            eprintln!("{}:{}: {}", path.display(), number, message);
+        }
+    }
+    match errors.len() {
+        0 => Type::Null,
+        count => ShellError::Runtime {
+            message: format!("Found {} syntax error(s) in {}", count, path.display()),
+            code: BuiltinExitCode::ParseError,
+        }
+        .into(),
+    }
+}
+
+pub fn run_file(path: &Path) -> Type {
+    match std::fs::read_to_string(path) {
+        Ok(source) => run_script(&source),
+        Err(e) => ShellError::from(e).into(),
+    }
+}
+
+pub fn rc_path() -> Option<PathBuf> {
+    let home = match my_home() {
+        Ok(Some(home)) => home,
+        _ => return None,
+    };
+    [".config/hug_shell/config.hug", ".hugrc"]
+        .iter()
+        .map(|path| home.join(path))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_line() {
+        assert_eq!(run_line("1; 'two'"), Type::String("two".to_string()));
+        assert_eq!(run_line(""), Type::Null);
+    }
+
+    #[test]
+    fn test_run_line_syntax_error() {
+        match run_line("'two") {
+            Type::Error { code, .. } => assert_eq!(code, BuiltinExitCode::SyntaxError as i32),
+            output => panic!("Expected Error, got {}", output),
+        }
+    }
+
+    #[test]
+    fn test_run_line_diagnostic() {
+        match run_line("ls; cd >") {
+            Type::Error { message, .. } => assert_eq!(
+                message,
+                "Syntax Error: Expected a destination after `>`\n1 | ls; cd >\n  |         ^"
+            ),
+            output => panic!("Expected Error, got {}", output),
+        }
+    }
+
+    #[test]
+    fn test_run_line_sourced_diagnostic() {
+        let path = std::env::temp_dir().join("hug_shell_test_sourced_diagnostic.hug");
+        std::fs::write(&path, "ls >").unwrap();
+        let output = run_line(&format!("\"ééé\"; source {}", path.display()));
+        std::fs::remove_file(path).unwrap();
+        match output {
+            Type::Error { message, span, .. } => {
+                assert!(message.ends_with("\n1 | ls >\n  |     ^"), "{}", message);
+                assert_eq!(span, None);
+            }
+            output => panic!("Expected Error, got {}", output),
+        }
+    }
+
+    #[test]
+    fn test_run_line_stops_at_syntax_error() {
+        let path = std::env::temp_dir().join("hug_shell_test_stops_at_syntax_error");
+        let line = format!("ls >; ls > {}", path.display());
+        assert_eq!(run_line(&line).exit_code(), 5);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_check_script() {
+        let errors = check_script("ls >; pwd |\n\ncat <<EOF\n>\nEOF\nls; | wc\n>");
+        assert_eq!(
+            errors
+                .iter()
+                .map(|(line, error)| (*line, error.exit_code()))
+                .collect::<Vec<(usize, i32)>>(),
+            vec![(1, 5), (6, 5), (7, 5)]
+        );
+        // A group left open at the end of the script is reported on its last line
+        match &check_script("ls\n(ls\n")[..] {
+            [(2, Type::Error { message, .. })] => {
+                assert!(message.ends_with("2 | (ls\n  |    ^"), "{}", message)
+            }
+            errors => panic!("Expected one error on line 2, got {:?}", errors),
+        }
+        // An unterminated string runs to the end of the script
+        assert_eq!(check_script("ls\necho 'hi\npwd")[0].0, 2);
+    }
+
+    #[test]
+    fn test_run_script() {
+        assert_eq!(run_script("1\n\n'two'\n"), Type::String("two".to_string()));
+    }
+
+    #[test]
+    fn test_run_script_tab_indentation() {
+        match run_script("{\n\techo hi\t# greet\r\n}\n") {
+            Type::Output(output) => assert_eq!(output.stdout, b"hi\n"),
+            output => panic!("Expected Output, got {}", output),
+        }
+    }
+
+    #[test]
+    fn test_run_script_heredoc() {
+        match run_script("wc -l <<EOF\na\n\nb\nEOF\n") {
+            Type::Output(output) => {
+                assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), "3");
+            }
+            output => panic!("Expected Output, got {}", output),
+        }
+    }
+}
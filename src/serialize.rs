@@ -0,0 +1,336 @@
+use std::path::Path;
+
+use crate::error::ShellError;
+use crate::typesystem::Type;
+
+// How values are written to the stdin of an external command, set with e.g.
+//   HUG_STDIN_FORMAT="json"
+// Output is always passed on as the raw bytes the previous command wrote
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Format {
+    // Strings as they are, other values one per line
+    #[default]
+    Text,
+    Json,
+    // One row per array item, arrays of arrays become rows of fields and arrays of records get
+    // a header row
+    Csv,
+    // Only records can be documents, fields that are null are left out
+    Toml,
+    Yaml,
+}
+
+// Every name `from_name` accepts, aliases come after the name shown in messages
+const FORMAT_NAMES: [(&str, Format); 6] = [
+    ("text", Format::Text),
+    ("json", Format::Json),
+    ("csv", Format::Csv),
+    ("toml", Format::Toml),
+    ("yaml", Format::Yaml),
+    ("yml", Format::Yaml),
+];
+
+impl Format {
+    pub fn from_env() -> Self {
+        std::env::var("HUG_STDIN_FORMAT")
+            .ok()
+            .and_then(|name| Format::from_name(name.trim()))
+            .unwrap_or_default()
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        FORMAT_NAMES
+            .iter()
+            .find(|(format_name, _)| format_name.eq_ignore_ascii_case(name))
+            .map(|(_, format)| *format)
+    }
+
+    // e.g. "text, json, csv, toml or yaml"
+    pub fn names() -> String {
+        let mut names = Vec::new();
+        for (i, (name, format)) in FORMAT_NAMES.iter().enumerate() {
+            if !FORMAT_NAMES[..i].iter().any(|(_, seen)| seen == format) {
+                names.push(*name);
+            }
+        }
+        let last = names.pop().unwrap_or_default();
+        format!("{} or {}", names.join(", "), last)
+    }
+
+    // Files that aren't .json, .csv, .toml or .yaml are written as text
+    pub fn from_extension(path: &Path) -> Self {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Format::from_name)
+            .unwrap_or_default()
+    }
+}
+
+impl Type {
+    pub fn serialize(&self, format: Format) -> Result<Vec<u8>, ShellError> {
+        match self {
+            Type::Output(o) => Ok(o.stdout.clone()),
+            Type::Binary(b) => Ok(b.clone()),
+            Type::Null | Type::Error { .. } => Ok(Vec::new()),
+            value => value.to_format(format, false).map(String::into_bytes),
+        }
+    }
+
+    // Unlike stdin, errors are written like any other value, as `{message, code}`
+    pub fn to_format(&self, format: Format, pretty: bool) -> Result<String, ShellError> {
+        match format {
+            Format::Text => Ok(to_text(self)),
+            Format::Json if pretty => Ok(format!("{:#}\n", to_json(self))),
+            Format::Json => Ok(format!("{}\n", to_json(self))),
+            Format::Csv => to_csv(self),
+            Format::Toml => match to_toml(self)? {
+                toml::Value::Table(table) if pretty => toml::to_string_pretty(&table),
+                toml::Value::Table(table) => toml::to_string(&table),
+                _ => return Err(format_error("TOML", "only records can be documents")),
+            }
+            .map_err(|e| format_error("TOML", e)),
+            Format::Yaml => {
+                serde_yaml_ng::to_string(&to_yaml(self)).map_err(|e| format_error("YAML", e))
+            }
+        }
+    }
+}
+
+fn to_text(value: &Type) -> String {
+    match value {
+        Type::String(s) => s.clone(),
+        Type::Binary(b) => String::from_utf8_lossy(b).to_string(),
+        Type::Array(a) => a
+            .iter()
+            .map(|item| {
+                let text = to_text(item);
+                match text.ends_with('\n') {
+                    true => text,
+                    false => text + "\n",
+                }
+            })
+            .collect(),
+        Type::Record(r) => r
+            .iter()
+            .map(|(key, value)| format!("{}: {}\n", key, to_text(value).trim_end()))
+            .collect(),
+        Type::Null | Type::Error { .. } => String::new(),
+        value => value.to_undecorated_string() + "\n",
+    }
+}
+
+fn to_json(value: &Type) -> serde_json::Value {
+    match value {
+        Type::String(s) => serde_json::Value::String(s.clone()),
+        Type::Integer(i) => serde_json::Value::from(*i),
+        Type::Float(fl) => serde_json::Number::from_f64(*fl)
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
+        Type::Boolean(b) => serde_json::Value::Bool(*b),
+        // Interchange formats have no bytes, so they're written as an array of numbers
+        Type::Binary(b) => serde_json::Value::from(b.as_slice()),
+        Type::Array(a) => serde_json::Value::Array(a.iter().map(to_json).collect()),
+        Type::Record(r) => serde_json::Value::Object(
+            r.iter()
+                .map(|(key, value)| (key.clone(), to_json(value)))
+                .collect(),
+        ),
+        Type::Output(_) | Type::File { .. } => serde_json::Value::String(to_field(value)),
+        Type::Error { message, code, .. } => serde_json::json!({"message": message, "code": code}),
+        Type::Null => serde_json::Value::Null,
+    }
+}
+
+fn to_toml(value: &Type) -> Result<toml::Value, ShellError> {
+    Ok(match value {
+        Type::String(s) => toml::Value::String(s.clone()),
+        Type::Integer(i) => toml::Value::Integer(*i),
+        Type::Float(fl) => toml::Value::Float(*fl),
+        Type::Boolean(b) => toml::Value::Boolean(*b),
+        Type::Binary(b) => {
+            toml::Value::Array(b.iter().map(|b| toml::Value::Integer(*b as i64)).collect())
+        }
+        Type::Array(a) => toml::Value::Array(a.iter().map(to_toml).collect::<Result<_, _>>()?),
+        Type::Record(r) => toml::Value::Table(
+            r.iter()
+                .filter(|(_, value)| *value != Type::Null)
+                .map(|(key, value)| Ok((key.clone(), to_toml(value)?)))
+                .collect::<Result<_, ShellError>>()?,
+        ),
+        Type::Output(_) | Type::File { .. } => toml::Value::String(to_field(value)),
+        Type::Error { message, code, .. } => toml::Value::Table(toml::Table::from_iter([
+            ("message".to_string(), toml::Value::String(message.clone())),
+            ("code".to_string(), toml::Value::Integer(*code as i64)),
+        ])),
+        Type::Null => return Err(format_error("TOML", "there is no null value")),
+    })
+}
+
+fn to_yaml(value: &Type) -> serde_yaml_ng::Value {
+    match value {
+        Type::String(s) => serde_yaml_ng::Value::String(s.clone()),
+        Type::Integer(i) => serde_yaml_ng::Value::Number((*i).into()),
+        Type::Float(fl) => serde_yaml_ng::Value::Number((*fl).into()),
+        Type::Boolean(b) => serde_yaml_ng::Value::Bool(*b),
+        Type::Binary(b) => serde_yaml_ng::Value::Sequence(
+            b.iter()
+                .map(|b| serde_yaml_ng::Value::Number((*b).into()))
+                .collect(),
+        ),
+        Type::Array(a) => serde_yaml_ng::Value::Sequence(a.iter().map(to_yaml).collect()),
+        Type::Record(r) => serde_yaml_ng::Value::Mapping(
+            r.iter()
+                .map(|(key, value)| (serde_yaml_ng::Value::String(key.clone()), to_yaml(value)))
+                .collect(),
+        ),
+        Type::Output(_) | Type::File { .. } => serde_yaml_ng::Value::String(to_field(value)),
+        Type::Error { message, code, .. } => serde_yaml_ng::Value::Mapping(
+            [
+                ("message", serde_yaml_ng::Value::String(message.clone())),
+                ("code", serde_yaml_ng::Value::Number((*code).into())),
+            ]
+            .into_iter()
+            .map(|(key, value)| (serde_yaml_ng::Value::String(key.to_string()), value))
+            .collect(),
+        ),
+        Type::Null => serde_yaml_ng::Value::Null,
+    }
+}
+
+fn to_csv(value: &Type) -> Result<String, ShellError> {
+    let items = match value {
+        Type::Array(a) => a.iter().collect(),
+        value => vec![value],
+    };
+    let mut rows = Vec::new();
+    if let Some(Type::Record(first)) = items.first() {
+        rows.push(first.iter().map(|(key, _)| key.clone()).collect());
+    }
+    for item in items {
+        rows.push(match item {
+            Type::Array(fields) => fields.iter().map(to_field).collect(),
+            Type::Record(fields) => fields.iter().map(|(_, value)| to_field(value)).collect(),
+            item => vec![to_field(item)],
+        });
+    }
+
+    let mut writer = csv::WriterBuilder::new()
+        .flexible(true)
+        .from_writer(Vec::new());
+    for row in rows {
+        writer
+            .write_record(row)
+            .map_err(|e| format_error("CSV", e))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| format_error("CSV", e.into_error()))?;
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+fn to_field(value: &Type) -> String {
+    match value {
+        Type::String(s) => s.clone(),
+        Type::File { path, .. } => path.to_string_lossy().to_string(),
+        Type::Null | Type::Error { .. } => String::new(),
+        value => value.to_undecorated_string(),
+    }
+}
+
+fn format_error(format: &str, e: impl std::fmt::Display) -> ShellError {
+    ShellError::Type {
+        message: format!("Couldn't write {}: {}", format, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serialize(value: Type, format: Format) -> String {
+        String::from_utf8(value.serialize(format).unwrap()).unwrap()
+    }
+
+    fn array() -> Type {
+        Type::Array(vec![
+            Type::String("a b".to_string()),
+            Type::Integer(1),
+            Type::Boolean(true),
+        ])
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Format::from_name("JSON"), Some(Format::Json));
+        assert_eq!(Format::from_name("csv"), Some(Format::Csv));
+        assert_eq!(Format::from_name("xml"), None);
+        assert_eq!(Format::names(), "text, json, csv, toml or yaml");
+    }
+
+    #[test]
+    fn test_serialize_text() {
+        assert_eq!(
+            serialize(Type::String("hi".to_string()), Format::Text),
+            "hi"
+        );
+        assert_eq!(serialize(array(), Format::Text), "a b\n1\ntrue\n");
+        assert_eq!(serialize(Type::Float(1.5), Format::Text), "1.5\n");
+        assert_eq!(serialize(Type::Null, Format::Text), "");
+    }
+
+    #[test]
+    fn test_serialize_output() {
+        let output = std::process::Command::new("printf")
+            .arg("\\x00\\xff")
+            .output()
+            .unwrap();
+        assert_eq!(
+            Type::Output(output).serialize(Format::Json).unwrap(),
+            vec![0, 255]
+        );
+    }
+
+    #[test]
+    fn test_serialize_binary() {
+        let binary = Type::Binary(vec![0, 255]);
+        assert_eq!(binary.serialize(Format::Csv).unwrap(), vec![0, 255]);
+        assert_eq!(binary.to_format(Format::Json, false).unwrap(), "[0,255]\n");
+    }
+
+    #[test]
+    fn test_serialize_json() {
+        assert_eq!(serialize(array(), Format::Json), "[\"a b\",1,true]\n");
+        let record = Type::Record(vec![
+            ("b".to_string(), Type::Null),
+            ("a".to_string(), array()),
+        ]);
+        assert_eq!(
+            serialize(record, Format::Json),
+            "{\"b\":null,\"a\":[\"a b\",1,true]}\n"
+        );
+        assert_eq!(
+            serialize(Type::String("\"q\"".to_string()), Format::Json),
+            "\"\\\"q\\\"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_serialize_csv() {
+        assert_eq!(serialize(array(), Format::Csv), "a b\n1\ntrue\n");
+        let rows = Type::Array(vec![
+            Type::Array(vec![Type::String("a,b".to_string()), Type::Integer(1)]),
+            Type::Array(vec![Type::String("c".to_string())]),
+        ]);
+        assert_eq!(serialize(rows, Format::Csv), "\"a,b\",1\nc\n");
+        let records = Type::Array(vec![
+            Type::Record(vec![
+                ("name".to_string(), Type::String("a".to_string())),
+                ("size".to_string(), Type::Integer(1)),
+            ]),
+            Type::Record(vec![
+                ("name".to_string(), Type::String("b".to_string())),
+                ("size".to_string(), Type::Integer(2)),
+            ]),
+        ]);
+        assert_eq!(serialize(records, Format::Csv), "name,size\na,1\nb,2\n");
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use crate::builtin::BuiltinExitCode;
+use crate::command::Command;
+use crate::error::ShellError;
+use crate::script::run_file;
+use crate::typesystem::Type;
+
+impl Command {
+    pub fn run_source(&self) -> Type {
+        let args = self.get_args();
+        match args.len() {
+            0 => ShellError::Runtime {
+                message: "Missing file to source".into(),
+                code: BuiltinExitCode::TooFewArguments,
+            }
+            .into(),
+            1 => match args[0].run_as_arg() {
+                Ok(path) => run_file(&PathBuf::from(path)),
+                Err(e) => e,
+            },
+            _ => ShellError::Runtime {
+                message: "Too many arguments".into(),
+                code: BuiltinExitCode::TooManyArguments,
+            }
+            .into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builtin::{Builtin, BuiltinExitCode};
+    use crate::command::{Command, CommandKind};
+    use crate::typesystem::Type;
+
+    #[test]
+    fn test_run() {
+        let path = std::env::temp_dir().join("hug_shell_test_source.hug");
+        std::fs::write(&path, "'first'\n42\n").unwrap();
+
+        let output = Command::new(CommandKind::Builtin {
+            builtin: Builtin::Source,
+            args: vec![Command::new(CommandKind::String(
+                path.to_string_lossy().to_string(),
+            ))],
+        })
+        .run();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(output, Type::Integer(42));
+    }
+
+    #[test]
+    fn test_run_with_invalid_arg() {
+        let output = Command::new(CommandKind::Builtin {
+            builtin: Builtin::Source,
+            args: vec![Command::new(CommandKind::String("invalid.hug".to_string()))],
+        })
+        .run();
+        match output {
+            Type::Error { code, .. } => {
+                assert_eq!(code, BuiltinExitCode::FileNotFound as i32);
+            }
+            _ => panic!("Expected Error, got {}", output),
+        }
+    }
+
+    #[test]
+    fn test_run_without_args() {
+        let output = Command::new(CommandKind::Builtin {
+            builtin: Builtin::Source,
+            args: vec![],
+        })
+        .run();
+        match output {
+            Type::Error { code, .. } => {
+                assert_eq!(code, BuiltinExitCode::TooFewArguments as i32);
+            }
+            _ => panic!("Expected Error, got {}", output),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// Byte offsets into the source a token or error came from, and the line it starts on
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span {
+            start,
+            end,
+            line: 0,
+        }
+    }
+
+    // Lines are counted from 1
+    pub fn locate(self, source: &str) -> Self {
+        let start = char_boundary(source, self.start);
+        Span {
+            line: source[..start].matches('\n').count() + 1,
+            ..self
+        }
+    }
+}
+
+// Renders the source line containing the span with a caret underline below it, e.g.
+//   3 | ls >
+//     |    ^
+pub fn underline(source: &str, span: Span) -> String {
+    let start = char_boundary(source, span.start);
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[start..]
+        .find('\n')
+        .map_or(source.len(), |i| start + i);
+    let end = char_boundary(source, span.end.clamp(start, line_end));
+
+    let line = &source[line_start..line_end];
+    let number = span.locate(source).line.to_string();
+    let padding = source[line_start..start].chars().count();
+    let width = source[start..end].chars().count().max(1);
+    format!(
+        "{} | {}\n{} | {}{}",
+        number,
+        line,
+        " ".repeat(number.len()),
+        " ".repeat(padding),
+        "^".repeat(width)
+    )
+}
+
+// Spans from another source can point anywhere, so offsets are moved back to a char in this one
+fn char_boundary(source: &str, offset: usize) -> usize {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_underline() {
+        assert_eq!(underline("ls >", Span::new(4, 4)), "1 | ls >\n  |     ^");
+        assert_eq!(
+            underline("ls\necho 'hi' x", Span::new(8, 12)),
+            "2 | echo 'hi' x\n  |      ^^^^"
+        );
+        assert_eq!(underline("é", Span::new(1, 9)), "1 | é\n  | ^");
+    }
+
+    #[test]
+    fn test_locate() {
+        assert_eq!(Span::new(0, 2).locate("ls\npwd").line, 1);
+        assert_eq!(Span::new(3, 6).locate("ls\npwd").line, 2);
+    }
+}
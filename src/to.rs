@@ -0,0 +1,178 @@
+use crate::builtin::BuiltinExitCode;
+use crate::command::Command;
+use crate::error::ShellError;
+use crate::serialize::Format;
+use crate::typesystem::Type;
+
+impl Command {
+    // e.g. `ls | to json --pretty`, the flag can come before or after the format
+    pub fn run_to(&self) -> Type {
+        let args = match self.run_args() {
+            Ok(args) => args,
+            Err(e) => return e,
+        };
+        let (flags, names): (Vec<&String>, Vec<&String>) =
+            args.iter().partition(|arg| arg.starts_with("--"));
+
+        let mut pretty = false;
+        for flag in flags {
+            match flag.as_str() {
+                "--pretty" => pretty = true,
+                flag => {
+                    return ShellError::Type {
+                        message: format!("Unknown flag `{}`, expected `--pretty`", flag),
+                    }
+                    .into()
+                }
+            }
+        }
+        let format = match names.as_slice() {
+            [] => {
+                return ShellError::Runtime {
+                    message: format!("Expected a format: {}", Format::names()),
+                    code: BuiltinExitCode::TooFewArguments,
+                }
+                .into()
+            }
+            [name] => match Format::from_name(name) {
+                Some(format) => format,
+                None => {
+                    return ShellError::Type {
+                        message: format!("Unknown format `{}`, expected {}", name, Format::names()),
+                    }
+                    .into()
+                }
+            },
+            _ => {
+                return ShellError::Runtime {
+                    message: "Too many arguments".into(),
+                    code: BuiltinExitCode::TooManyArguments,
+                }
+                .into()
+            }
+        };
+
+        match self
+            .stdin
+            .as_ref()
+            .unwrap_or(&Type::Null)
+            .to_format(format, pretty)
+        {
+            Ok(s) => Type::String(s),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    use crate::from::InputFormat;
+    use crate::lexer::lex;
+    use crate::parser::Parser;
+
+    fn to(value: &Type, args: &str) -> Type {
+        let tokens = lex(&format!("to {}", args)).unwrap();
+        let mut command = Parser::new(tokens.iter().peekable()).parse().remove(0);
+        command.stdin = Some(value.clone());
+        command.run()
+    }
+
+    fn to_string(value: &Type, args: &str) -> String {
+        match to(value, args) {
+            Type::String(s) => s,
+            output => panic!("Expected Type::String, got {}", output),
+        }
+    }
+
+    fn record() -> Type {
+        Type::Record(vec![
+            ("name".to_string(), Type::String("hug".to_string())),
+            ("size".to_string(), Type::Integer(3)),
+            ("ratio".to_string(), Type::Float(0.5)),
+            ("ok".to_string(), Type::Boolean(true)),
+            (
+                "tags".to_string(),
+                Type::Array(vec![
+                    Type::String("a".to_string()),
+                    Type::String("b".to_string()),
+                ]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_run_round_trip() {
+        for (format, input) in [
+            ("json", InputFormat::Json),
+            ("toml", InputFormat::Toml),
+            ("yaml", InputFormat::Yaml),
+        ] {
+            for pretty in ["", "--pretty"] {
+                let text = to_string(&record(), &format!("{} {}", format, pretty));
+                assert_eq!(input.parse(&text).unwrap(), record(), "{}", text);
+            }
+        }
+
+        let rows = Type::Array(
+            vec![record(), record()]
+                .into_iter()
+                .map(|row| match row {
+                    Type::Record(fields) => Type::Record(fields[..4].to_vec()),
+                    _ => unreachable!(),
+                })
+                .collect(),
+        );
+        let text = to_string(&rows, "csv");
+        assert_eq!(InputFormat::Csv.parse(&text).unwrap(), rows);
+    }
+
+    #[test]
+    fn test_run_pretty() {
+        let value = Type::Array(vec![Type::Integer(1), Type::Null]);
+        assert_eq!(to_string(&value, "json"), "[1,null]\n");
+        assert_eq!(to_string(&value, "--pretty json"), "[\n  1,\n  null\n]\n");
+    }
+
+    #[test]
+    fn test_run_file_and_error() {
+        let file = Type::File {
+            path: PathBuf::from("./src/main.rs"),
+            full_path: false,
+        };
+        assert_eq!(to_string(&file, "json"), "\"./src/main.rs\"\n");
+
+        let error: Type = ShellError::Type {
+            message: "oops".to_string(),
+        }
+        .into();
+        assert_eq!(
+            to_string(&error, "json"),
+            "{\"message\":\"oops\",\"code\":4}\n"
+        );
+        assert_eq!(to_string(&error, "yaml"), "message: oops\ncode: 4\n");
+    }
+
+    #[test]
+    fn test_run_invalid() {
+        assert_eq!(
+            to(&Type::Integer(1), "toml").exit_code(),
+            BuiltinExitCode::InvalidArgument as i32
+        );
+        assert_eq!(
+            to(&Type::Integer(1), "xml").exit_code(),
+            BuiltinExitCode::InvalidArgument as i32
+        );
+        assert_eq!(
+            to(&Type::Integer(1), "json --compact").exit_code(),
+            BuiltinExitCode::InvalidArgument as i32
+        );
+        assert_eq!(
+            to(&Type::Integer(1), "").exit_code(),
+            BuiltinExitCode::TooFewArguments as i32
+        );
+    }
+}
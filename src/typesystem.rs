@@ -2,22 +2,37 @@ use colored::Colorize;
 
 use std::fs::File;
 use std::os::unix::fs::PermissionsExt;
-use std::path::PathBuf;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+
+use crate::span::{underline, Span};
 
 #[derive(Debug, Clone)]
 pub enum Type {
     Output(std::process::Output),
 
-    File { path: PathBuf, full_path: bool },
+    File {
+        path: PathBuf,
+        full_path: bool,
+    },
 
     String(String),
+    // Content that isn't text, e.g. an image read with `open`
+    Binary(Vec<u8>),
     Array(Vec<Type>),
+    // Fields keep the order they were parsed or built in
+    Record(Vec<(String, Type)>),
     Integer(i64),
     Float(f64),
     Boolean(bool),
     Null,
 
-    Error { message: String, code: i32 },
+    // Errors from the source, e.g. a syntax error, point at the offending span
+    Error {
+        message: String,
+        code: i32,
+        span: Option<Span>,
+    },
 }
 
 impl PartialEq for Type {
@@ -35,10 +50,18 @@ impl PartialEq for Type {
                 Type::String(s2) => s == s2,
                 _ => false,
             },
+            Type::Binary(b) => match other {
+                Type::Binary(b2) => b == b2,
+                _ => false,
+            },
             Type::Array(a) => match other {
                 Type::Array(a2) => a == a2,
                 _ => false,
             },
+            Type::Record(r) => match other {
+                Type::Record(r2) => r == r2,
+                _ => false,
+            },
             Type::Integer(i) => match other {
                 Type::Integer(i2) => i == i2,
                 _ => false,
@@ -51,14 +74,12 @@ impl PartialEq for Type {
                 Type::Boolean(b2) => b == b2,
                 _ => false,
             },
-            Type::Null => match other {
-                Type::Null => true,
-                _ => false,
-            },
-            Type::Error { message, code } => match other {
+            Type::Null => matches!(other, Type::Null),
+            Type::Error { message, code, .. } => match other {
                 Type::Error {
                     message: m2,
                     code: c2,
+                    ..
                 } => message == m2 && code == c2,
                 _ => false,
             },
@@ -69,24 +90,23 @@ impl PartialEq for Type {
 impl std::fmt::Display for Type {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Type::Output(o) => match o.status.success() {
-                true => write!(f, "{}", String::from_utf8_lossy(&o.stdout)),
-                false => write!(f, "{}", String::from_utf8_lossy(&o.stderr)),
-            },
+            Type::Output(o) => write!(f, "{}", output_to_string(o)),
 
             Type::File { path, full_path } => color_file(path, f, *full_path),
 
-            Type::String(s) => write!(f, "{}", format!("\"{s}\"").green().to_string()),
-            Type::Array(a) => write!(f, "{}", array_to_string(a, true).to_string()),
-            Type::Integer(i) => write!(f, "{}", i.to_string().cyan().to_string()),
-            Type::Float(fl) => write!(f, "{}", fl.to_string().cyan().to_string()),
-            Type::Boolean(b) => write!(f, "{}", b.to_string().bright_magenta().to_string()),
-            Type::Null => write!(f, "{}", "null".to_string().yellow().to_string()),
+            Type::String(s) => write!(f, "{}", format!("\"{s}\"").green()),
+            Type::Binary(b) => write!(f, "{}", bytes_to_string(b)),
+            Type::Array(a) => write!(f, "{}", array_to_string(a, true)),
+            Type::Record(r) => write!(f, "{}", record_to_string(r, true)),
+            Type::Integer(i) => write!(f, "{}", i.to_string().cyan()),
+            Type::Float(fl) => write!(f, "{}", fl.to_string().cyan()),
+            Type::Boolean(b) => write!(f, "{}", b.to_string().bright_magenta()),
+            Type::Null => write!(f, "{}", "null".to_string().yellow()),
 
-            Type::Error { message, code } => write!(
+            Type::Error { message, code, .. } => write!(
                 f,
                 "{}{}\nExited With status {}",
-                "Error: ".red().to_string(),
+                "Error: ".red(),
                 message,
                 code
             ),
@@ -95,31 +115,50 @@ impl std::fmt::Display for Type {
 }
 
 impl Type {
-    pub fn to_colorless_string(&self) -> String {
+    // Adds the source line and a caret under the span to the message of a spanned error
+    pub fn with_source(self, source: &str) -> Type {
+        match self {
+            Type::Error {
+                message,
+                code,
+                span: Some(span),
+            } => Type::Error {
+                message: format!("{}\n{}", message, underline(source, span)),
+                code,
+                // Rendered once, so callers further out don't underline it in their own source
+                span: None,
+            },
+            output => output,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
         match self {
-            Type::Output(o) => match o.status.success() {
-                true => String::from_utf8_lossy(&o.stdout).to_string(),
-                false => String::from_utf8_lossy(&o.stderr).to_string(),
+            Type::Output(o) => match o.status.code() {
+                Some(code) => code,
+                None => 128 + o.status.signal().unwrap_or(0),
             },
+            Type::Error { code, .. } => *code,
+            _ => 0,
+        }
+    }
 
-            Type::File {
-                path, full_path, ..
-            } => {
-                if *full_path {
-                    path.to_str().unwrap().to_string()
-                } else {
-                    path.file_name().unwrap().to_str().unwrap().to_string()
-                }
-            }
+    pub fn to_colorless_string(&self) -> String {
+        match self {
+            Type::Output(o) => output_to_string(o),
+
+            Type::File { path, full_path } => file_name(path, *full_path),
 
             Type::String(s) => format!("\"{s}\""),
+            Type::Binary(b) => bytes_to_string(b),
             Type::Array(a) => array_to_string(a, false),
+            Type::Record(r) => record_to_string(r, false),
             Type::Integer(i) => i.to_string(),
             Type::Float(fl) => fl.to_string(),
             Type::Boolean(b) => b.to_string(),
             Type::Null => "null".to_string(),
 
-            Type::Error { message, code } => {
+            Type::Error { message, code, .. } => {
                 format!("Error: {}\nExited With status {}", message, code)
             }
         }
@@ -127,44 +166,35 @@ impl Type {
 
     pub fn to_undecorated_string(&self) -> String {
         match self {
-            Type::Output(o) => match o.status.success() {
-                true => String::from_utf8_lossy(&o.stdout).to_string(),
-                false => String::from_utf8_lossy(&o.stderr).to_string(),
-            },
+            Type::Output(o) => String::from_utf8_lossy(&o.stdout).to_string(),
 
-            Type::File {
-                path, full_path, ..
-            } => {
-                if *full_path {
-                    path.to_str().unwrap().to_string()
-                } else {
-                    path.file_name().unwrap().to_str().unwrap().to_string()
-                }
-            }
+            Type::File { path, full_path } => file_name(path, *full_path),
 
             Type::String(s) => s.to_string(),
+            Type::Binary(b) => String::from_utf8_lossy(b).to_string(),
             Type::Array(a) => array_to_string(a, false),
+            Type::Record(r) => record_to_string(r, false),
             Type::Integer(i) => i.to_string(),
             Type::Float(fl) => fl.to_string(),
             Type::Boolean(b) => b.to_string(),
             Type::Null => "null".to_string(),
 
-            Type::Error { message, code } => {
+            Type::Error { message, code, .. } => {
                 format!("Error: {}\nExited With status {}", message, code)
             }
         }
     }
 }
 
-fn array_to_string(array: &Vec<Type>, colored: bool) -> String {
+fn array_to_string(array: &[Type], colored: bool) -> String {
     let mut string = String::new();
     string.push_str("[\n");
     for (i, item) in array.iter().enumerate() {
         string.push_str("  ");
         if colored {
-            string.push_str(&item.to_string());
+            string.push_str(&indent(&item.to_string()));
         } else {
-            string.push_str(&item.to_colorless_string());
+            string.push_str(&indent(&item.to_colorless_string()));
         }
         if i < array.len() - 1 {
             string.push_str(",\n");
@@ -174,16 +204,79 @@ fn array_to_string(array: &Vec<Type>, colored: bool) -> String {
     string
 }
 
+fn record_to_string(record: &[(String, Type)], colored: bool) -> String {
+    let fields = record
+        .iter()
+        .map(|(key, value)| match colored {
+            true => format!("  {}: {}", key, indent(&value.to_string())),
+            false => format!("  {}: {}", key, indent(&value.to_colorless_string())),
+        })
+        .collect::<Vec<String>>();
+    format!("{{\n{}\n}}", fields.join(",\n"))
+}
+
+// Both streams of a command, stderr after stdout
+fn output_to_string(output: &std::process::Output) -> String {
+    let stdout = bytes_to_string(&output.stdout);
+    match output.stderr.is_empty() {
+        true => stdout,
+        false if stdout.is_empty() || stdout.ends_with('\n') => {
+            stdout + &bytes_to_string(&output.stderr)
+        }
+        false => stdout + "\n" + &bytes_to_string(&output.stderr),
+    }
+}
+
+// Bytes that aren't UTF-8 are previewed as a hex dump like `xxd`'s, 16 bytes a line
+pub fn bytes_to_string(bytes: &[u8]) -> String {
+    const PREVIEW_LINES: usize = 8;
+
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+    let mut lines = bytes
+        .chunks(16)
+        .take(PREVIEW_LINES)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex = chunk
+                .chunks(2)
+                .map(|pair| pair.iter().map(|b| format!("{:02x}", b)).collect())
+                .collect::<Vec<String>>()
+                .join(" ");
+            let ascii = chunk
+                .iter()
+                .map(|b| match b.is_ascii_graphic() || *b == b' ' {
+                    true => *b as char,
+                    false => '.',
+                })
+                .collect::<String>();
+            format!("{:08x}: {:<39}  {}", i * 16, hex, ascii)
+        })
+        .collect::<Vec<String>>();
+    lines.push(format!("({} bytes of binary data)", bytes.len()));
+    lines.join("\n")
+}
+
+// Nested arrays and records line up with the item they're in
+fn indent(item: &str) -> String {
+    item.replace('\n', "\n  ")
+}
+
+// Paths without a file name, e.g. `/` or `..`, are shown in full
+fn file_name(path: &Path, full_path: bool) -> String {
+    match path.file_name() {
+        Some(name) if !full_path => name.to_string_lossy().to_string(),
+        _ => path.to_string_lossy().to_string(),
+    }
+}
+
 fn color_file(
     path: &PathBuf,
     f: &mut std::fmt::Formatter<'_>,
     full_path: bool,
 ) -> Result<(), std::fmt::Error> {
-    let path_name = if full_path {
-        path.to_str().unwrap()
-    } else {
-        path.file_name().unwrap().to_str().unwrap()
-    };
+    let path_name = file_name(path, full_path);
     let file = match File::open(path) {
         Ok(file) => file,
         Err(e) => {
@@ -195,17 +288,17 @@ fn color_file(
             if metadata.is_dir() {
                 if path_name.starts_with(".") {
                     // Faded blue
-                    write!(f, "{}", path_name.bright_blue().to_string())
+                    write!(f, "{}", path_name.bright_blue())
                 } else {
-                    write!(f, "{}", path_name.blue().to_string())
+                    write!(f, "{}", path_name.blue())
                 }
             } else if metadata.is_file() {
                 if path_name.starts_with(".") {
-                    write!(f, "{}", path_name.bright_green().to_string())
+                    write!(f, "{}", path_name.bright_green())
                 } else if metadata.permissions().mode() & 0o111 != 0 {
-                    write!(f, "{}", path_name.yellow().to_string())
+                    write!(f, "{}", path_name.yellow())
                 } else {
-                    write!(f, "{}", path_name.green().to_string())
+                    write!(f, "{}", path_name.green())
                 }
             } else {
                 write!(f, "{:?}", path_name)
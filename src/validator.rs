@@ -0,0 +1,77 @@
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+
+use crate::helper::ShellHelper;
+use crate::lexer::{lex, TokenKind, UNTERMINATED_HEREDOC, UNTERMINATED_STRING};
+use crate::parser::Parser;
+
+impl Validator for ShellHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        if is_incomplete(ctx.input()) {
+            Ok(ValidationResult::Incomplete)
+        } else {
+            Ok(ValidationResult::Valid(None))
+        }
+    }
+}
+
+// Input is incomplete if it ends inside a string or heredoc, after a `|`, `&&`, `||` or `\`, or
+// inside a `{` block or `(` subshell the parser couldn't close
+fn is_incomplete(input: &str) -> bool {
+    let tokens = match lex(input) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            let message = e.to_string();
+            return message == UNTERMINATED_STRING || message == UNTERMINATED_HEREDOC;
+        }
+    };
+
+    let mut parser = Parser::new(tokens.iter().peekable());
+    parser.parse();
+
+    match tokens.iter().rfind(|t| t.kind != TokenKind::Newline) {
+        Some(token) if matches!(token.kind, TokenKind::Pipe | TokenKind::And | TokenKind::Or) => {
+            true
+        }
+        // Only a bare `\` continues the line, not an escaped one like `a\\` or one in a comment
+        Some(token) if token.kind == TokenKind::Word => {
+            let text = input
+                .get(token.span.start..token.span.end)
+                .unwrap_or_default();
+            text.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1 || parser.is_unclosed()
+        }
+        _ => parser.is_unclosed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_incomplete() {
+        assert!(is_incomplete("echo 'Hello,"));
+        assert!(is_incomplete("echo \"Hello,\nWorld!"));
+        assert!(is_incomplete("ls |"));
+        assert!(is_incomplete("ls &&"));
+        assert!(is_incomplete("ls \\"));
+        assert!(is_incomplete("{ ls"));
+        assert!(is_incomplete("{ ls\n{ pwd }"));
+        assert!(is_incomplete("(cd /tmp"));
+        assert!(is_incomplete("cat <<EOF\nHello,"));
+    }
+
+    #[test]
+    fn test_is_complete() {
+        assert!(!is_incomplete(""));
+        assert!(!is_incomplete("echo 'Hello,\nWorld!'"));
+        assert!(!is_incomplete("ls |\ngrep src"));
+        assert!(!is_incomplete("ls \\\ntest_dir"));
+        assert!(!is_incomplete("{ ls\n}"));
+        assert!(!is_incomplete("ls;"));
+        assert!(!is_incomplete("echo a\\\\"));
+        assert!(!is_incomplete("echo {"));
+        assert!(!is_incomplete("echo } {"));
+        assert!(!is_incomplete("ls # a comment \\"));
+        assert!(!is_incomplete("cat <<EOF\nHello,\nEOF"));
+    }
+}